@@ -1,9 +1,26 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use common::{Dag, DagNode, JobInfo, JobRequest, JobResults, WorkerMetrics};
+use common::{Dag, DagNode, JobInfo, JobRequest, JobResults, ScheduleInfo, ScheduleRequest, WorkerMetrics};
 use reqwest::Client;
+use serde::Deserialize;
 use std::env;
 use common::engine;
+
+/// Respuesta de `GET /api/v1/tasks/{id}/log` (ver `master::handlers::get_task_log`).
+#[derive(Debug, Deserialize)]
+struct TaskLogResponse {
+    content: String,
+    next_offset: u64,
+}
+
+/// Un elemento de `GET /api/v1/background` (ver `master::background::BackgroundWorkerInfo`).
+#[derive(Debug, Deserialize)]
+struct BackgroundWorkerInfo {
+    name: String,
+    state: String,
+    last_tick_secs_ago: u64,
+    last_error: Option<String>,
+}
 /// Igual que en el worker:
 /// - En Docker: MASTER_URL=http://master:8080
 /// - Local: default http://localhost:8080
@@ -39,6 +56,124 @@ enum Commands {
 
     Workers,
 
+    /// Lista los workers en segundo plano del master (ver GET /api/v1/background)
+    Background,
+
+    /// Pausa un worker en segundo plano del master
+    BackgroundPause {
+        #[arg(value_name = "NOMBRE")]
+        name: String,
+    },
+
+    /// Reanuda un worker en segundo plano previamente pausado
+    BackgroundResume {
+        #[arg(value_name = "NOMBRE")]
+        name: String,
+    },
+
+    /// Cancela un worker en segundo plano (no se puede reanudar después)
+    BackgroundCancel {
+        #[arg(value_name = "NOMBRE")]
+        name: String,
+    },
+
+    /// Cancela un job: descarta sus tareas pendientes y en vuelo
+    Cancel {
+        #[arg(value_name = "JOB_ID")]
+        id: String,
+    },
+
+    /// Pausa un job: el scheduler deja de asignarle tareas nuevas
+    Pause {
+        #[arg(value_name = "JOB_ID")]
+        id: String,
+    },
+
+    /// Reanuda un job pausado
+    Resume {
+        #[arg(value_name = "JOB_ID")]
+        id: String,
+    },
+
+    /// Muestra el log de una tarea (ver `GET /api/v1/tasks/{id}/log`)
+    Logs {
+        #[arg(value_name = "TASK_ID")]
+        task_id: String,
+
+        /// Sigue el log a medida que la tarea progresa, en vez de
+        /// mostrarlo una sola vez (pollea con el `next_offset` devuelto).
+        #[arg(long)]
+        follow: bool,
+    },
+
+    /// Agenda el WordCount fijo para que corra de forma recurrente
+    ScheduleAdd {
+        #[arg(value_name = "NOMBRE")]
+        name: String,
+
+        /// Intervalo fijo en segundos entre corridas. Mutuamente
+        /// excluyente con --cron.
+        #[arg(long)]
+        interval_secs: Option<u64>,
+
+        /// Expresión cron de 5 campos ("min hora dia-mes mes dia-semana",
+        /// en UTC). Mutuamente excluyente con --interval-secs.
+        #[arg(long)]
+        cron: Option<String>,
+    },
+
+    /// Lista los jobs recurrentes agendados
+    ScheduleList,
+
+    /// Da de baja un job recurrente
+    ScheduleRm {
+        #[arg(value_name = "SCHEDULE_ID")]
+        id: String,
+    },
+
+    /// Dashboard en vivo de workers y jobs (refresca a intervalos)
+    Top {
+        /// Intervalo de refresco en milisegundos
+        #[arg(long, default_value_t = 1000)]
+        interval_ms: u64,
+    },
+
+    /// Demo: WordCount en un solo proceso, sin master/workers, con shuffle
+    /// a disco acotado en memoria (ver `engine::execute_wordcount_dag_for_file`):
+    /// combine del lado del map, particiones en formato binario, reduce con
+    /// `SpillingAggregator` (spill a disco cuando hay demasiadas claves
+    /// distintas) y ejecución paralela acotada entre particiones.
+    WordcountLocal {
+        /// Ruta o patrón glob de entrada (dentro del contenedor)
+        #[arg(value_name = "INPUT")]
+        input: String,
+
+        /// Directorio donde se escriben las particiones de shuffle y los
+        /// spills intermedios
+        #[arg(long, default_value = "/data/tmp/wordcount_local")]
+        tmp_dir: String,
+
+        /// Cantidad de particiones del shuffle
+        #[arg(long, default_value_t = 4)]
+        partitions: u32,
+
+        /// Ruta de salida CSV "token,count" (dentro del contenedor)
+        #[arg(long, default_value = "/data/output/wordcount_local.csv")]
+        output: String,
+
+        /// Desactiva el combine del lado del map antes del shuffle: sólo
+        /// hace falta para pipelines no sumables (WordCount es sum, así
+        /// que acá es puramente para comparar/depurar).
+        #[arg(long)]
+        no_combine: bool,
+
+        /// Corre el reduce de a una partición por vez en vez del pool
+        /// paralelo acotado (equivalente a MINISPARK_SEQUENTIAL=1, ver
+        /// `engine::reduce_partitions_to_file_with`): útil para debuggear.
+        #[arg(long)]
+        sequential: bool,
+    },
+
     /// Demo: join entre dos CSV por clave usando el engine local
     Join {
         /// Ruta al CSV de ventas (dentro del contenedor)
@@ -56,6 +191,21 @@ enum Commands {
         /// Ruta de salida JSONL (dentro del contenedor)
         #[arg(long, default_value = "/data/output/join_ventas_catalogo.jsonl")]
         output: String,
+
+        /// Usa el sort-merge externo por disco (ver
+        /// `engine::join_csv_files_shuffled_local`) en vez de
+        /// `join_csv_in_memory`: ninguno de los dos CSV necesita entrar
+        /// completo en memoria.
+        #[arg(long)]
+        spilling: bool,
+
+        /// Cantidad de particiones del shuffle (sólo con --spilling)
+        #[arg(long, default_value_t = 4)]
+        partitions: u32,
+
+        /// Directorio de shuffle/spill (sólo con --spilling)
+        #[arg(long, default_value = "/data/tmp/join_local")]
+        tmp_dir: String,
     },
 }
 
@@ -74,6 +224,7 @@ fn build_wordcount_dag() -> (Dag, String) {
         partitions: Some(4),              // mismo valor que parallelism
         fn_name: None,
         key: None,
+        fn_src: None,
     };
 
     // Nodo "flat": flat_map(tokenize)
@@ -84,6 +235,7 @@ fn build_wordcount_dag() -> (Dag, String) {
         partitions: None,
         fn_name: Some("tokenize".to_string()),
         key: None,
+        fn_src: None,
     };
 
     // Nodo "map1": map(to_lower)
@@ -94,6 +246,7 @@ fn build_wordcount_dag() -> (Dag, String) {
         partitions: None,
         fn_name: Some("to_lower".to_string()),
         key: None,
+        fn_src: None,
     };
 
     // Nodo "agg": reduce_by_key(sum) usando key="token"
@@ -104,6 +257,7 @@ fn build_wordcount_dag() -> (Dag, String) {
         partitions: None,
         fn_name: Some("sum".to_string()),
         key: Some("token".to_string()),
+        fn_src: None,
     };
 
     let dag = Dag {
@@ -119,6 +273,26 @@ fn build_wordcount_dag() -> (Dag, String) {
 }
 
 
+/// DAG mínimo para `WordcountLocal`: un solo nodo `read_text_glob`, ya que
+/// `engine::execute_wordcount_dag_for_file` sólo necesita de él el `op`
+/// (para elegir el formato de lectura) y `partitions` (para el shuffle).
+fn build_wordcount_local_dag(input: &str, partitions: u32) -> Dag {
+    let read = DagNode {
+        id: "read".to_string(),
+        op: "read_text_glob".to_string(),
+        path: Some(input.to_string()),
+        partitions: Some(partitions),
+        fn_name: None,
+        key: None,
+        fn_src: None,
+    };
+
+    Dag {
+        nodes: vec![read],
+        edges: vec![],
+    }
+}
+
 pub async fn run() -> Result<()> {
     let cli = Cli::parse();
     let client = Client::new();
@@ -169,11 +343,12 @@ pub async fn run() -> Result<()> {
                     job.total_tasks, job.completed_tasks, job.failed_tasks, job.retries
                 );
 
-                // progreso calculado localmente
-                let done = job.completed_tasks + job.failed_tasks;
+                // `job.progress` ya viene calculado por el master (ver
+                // `handlers::recompute_job_progress`): a diferencia de
+                // completed/total, también pondera las tareas en vuelo por
+                // su último `POST /api/v1/tasks/{id}/progress`.
                 if job.total_tasks > 0 {
-                    let pct = (done as f64 / job.total_tasks as f64) * 100.0;
-                    println!("  progreso: {:.1}%", pct);
+                    println!("  progreso: {:.1}%", job.progress * 100.0);
                 } else {
                     println!("  progreso: (sin tareas)");
                 }
@@ -213,14 +388,126 @@ pub async fn run() -> Result<()> {
             }
         }
 
-        Commands::Join { left, right, key, output } => {
+        Commands::ScheduleAdd { name, interval_secs, cron } => {
+            let url = format!("{}/api/v1/schedules", base_url);
+            let (dag, input_glob) = build_wordcount_dag();
+
+            let req = ScheduleRequest {
+                name,
+                dag,
+                parallelism: 4,
+                input_glob,
+                output_dir: "/data/output".to_string(),
+                interval_secs,
+                cron,
+            };
+
+            let resp = client.post(&url).json(&req).send().await?;
+            if resp.status().is_success() {
+                let info: ScheduleInfo = resp.json().await?;
+                println!("Schedule creado:");
+                println!("  id: {}", info.id);
+                println!("  nombre: {}", info.name);
+                println!("  spec: {}", info.spec);
+                println!("  next_run_secs: {}", info.next_run_secs);
+            } else {
+                println!("Error creando schedule (status {})", resp.status());
+            }
+        }
+
+        Commands::ScheduleList => {
+            let url = format!("{}/api/v1/schedules", base_url);
+            let resp = client.get(&url).send().await?;
+            if resp.status().is_success() {
+                let schedules: Vec<ScheduleInfo> = resp.json().await?;
+                if schedules.is_empty() {
+                    println!("No hay schedules agendados.");
+                } else {
+                    for s in schedules {
+                        println!("Schedule {}", s.id);
+                        println!("  nombre        : {}", s.name);
+                        println!("  spec          : {}", s.spec);
+                        println!("  input_glob    : {}", s.input_glob);
+                        println!("  output_dir    : {}", s.output_dir);
+                        println!("  next_run_secs : {}", s.next_run_secs);
+                        match s.last_job {
+                            Some(ref job_id) => println!("  last_job      : {}", job_id),
+                            None => println!("  last_job      : (ninguno todavía)"),
+                        }
+                        println!();
+                    }
+                }
+            } else {
+                println!(
+                    "Error consultando /api/v1/schedules (status {})",
+                    resp.status()
+                );
+            }
+        }
+
+        Commands::ScheduleRm { id } => {
+            let url = format!("{}/api/v1/schedules/{}", base_url, id);
+            let resp = client.delete(&url).send().await?;
+            if resp.status().is_success() {
+                println!("Schedule {} eliminado.", id);
+            } else {
+                println!("No se encontró el schedule {} (status {})", id, resp.status());
+            }
+        }
+
+        Commands::WordcountLocal { input, tmp_dir, partitions, output, no_combine, sequential } => {
+            println!("Ejecutando WordCount local (sin master/workers):");
+            println!("  input      : {}", input);
+            println!("  tmp_dir    : {}", tmp_dir);
+            println!("  partitions : {}", partitions);
+            println!("  output     : {}", output);
+            println!("  combine    : {}", !no_combine);
+            println!("  sequential : {}", sequential);
+
+            if sequential {
+                std::env::set_var("MINISPARK_SEQUENTIAL", "1");
+            }
+
+            let dag = build_wordcount_local_dag(&input, partitions);
+            if let Err(e) = engine::execute_wordcount_dag_for_file_with(
+                &dag,
+                &input,
+                &tmp_dir,
+                partitions,
+                &output,
+                !no_combine,
+            ) {
+                eprintln!("Error ejecutando wordcount local: {e:?}");
+                std::process::exit(1);
+            }
+
+            println!("WordCount local completado. Archivo de salida: {}", output);
+        }
+
+        Commands::Join { left, right, key, output, spilling, partitions, tmp_dir } => {
             println!("Ejecutando join local entre CSVs:");
             println!("  left : {}", left);
             println!("  right: {}", right);
             println!("  key  : {}", key);
             println!("  out  : {}", output);
 
-            if let Err(e) = engine::join_csv_in_memory(&left, &right, &key, &output) {
+            let result = if spilling {
+                println!("  modo : spilling (sort-merge externo, partitions={})", partitions);
+                engine::join_csv_files_shuffled_local(
+                    &left,
+                    &right,
+                    &key,
+                    &tmp_dir,
+                    partitions,
+                    &output,
+                    engine::JoinType::Inner,
+                )
+            } else {
+                println!("  modo : in-memory");
+                engine::join_csv_in_memory(&left, &right, &key, &output)
+            };
+
+            if let Err(e) = result {
                 eprintln!("Error ejecutando join: {e:?}");
                 std::process::exit(1);
             }
@@ -229,6 +516,70 @@ pub async fn run() -> Result<()> {
         }
 
 
+        Commands::Cancel { id } => {
+            let url = format!("{}/api/v1/jobs/{}/cancel", base_url, id);
+            let resp = client.post(&url).send().await?;
+            if resp.status().is_success() {
+                let job: JobInfo = resp.json().await?;
+                println!("Job {} cancelado (estado: {:?})", job.id, job.status);
+            } else {
+                println!("Error cancelando job {} (status {})", id, resp.status());
+            }
+        }
+
+        Commands::Pause { id } => {
+            let url = format!("{}/api/v1/jobs/{}/pause", base_url, id);
+            let resp = client.post(&url).send().await?;
+            if resp.status().is_success() {
+                let job: JobInfo = resp.json().await?;
+                println!("Job {} pausado (estado: {:?})", job.id, job.status);
+            } else {
+                println!("Error pausando job {} (status {})", id, resp.status());
+            }
+        }
+
+        Commands::Resume { id } => {
+            let url = format!("{}/api/v1/jobs/{}/resume", base_url, id);
+            let resp = client.post(&url).send().await?;
+            if resp.status().is_success() {
+                let job: JobInfo = resp.json().await?;
+                println!("Job {} reanudado (estado: {:?})", job.id, job.status);
+            } else {
+                println!("Error reanudando job {} (status {})", id, resp.status());
+            }
+        }
+
+        Commands::Logs { task_id, follow } => {
+            let mut offset: u64 = 0;
+            loop {
+                let url = format!("{}/api/v1/tasks/{}/log?offset={}", base_url, task_id, offset);
+                let resp = client.get(&url).send().await?;
+
+                if !resp.status().is_success() {
+                    println!(
+                        "No se encontró el log de la tarea {} (status {})",
+                        task_id,
+                        resp.status()
+                    );
+                    break;
+                }
+
+                let log: TaskLogResponse = resp.json().await?;
+                print!("{}", log.content);
+                offset = log.next_offset;
+
+                if !follow {
+                    break;
+                }
+                std::io::Write::flush(&mut std::io::stdout())?;
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            }
+        }
+
+        Commands::Top { interval_ms } => {
+            crate::tui::run(base_url, std::time::Duration::from_millis(interval_ms)).await?;
+        }
+
         Commands::Workers => {
             let url = format!("{}/api/v1/workers", base_url);
             let resp = client.get(&url).send().await?;
@@ -240,7 +591,7 @@ pub async fn run() -> Result<()> {
                     for w in workers {
                         println!("Worker {}", w.worker_id);
                         println!("  host           : {}", w.hostname);
-                        println!("  dead           : {}", w.dead);
+                        println!("  estado         : {:?}", w.state);
                         println!(
                             "  last_heartbeat : {} s ago",
                             w.last_heartbeat_secs_ago
@@ -268,6 +619,11 @@ pub async fn run() -> Result<()> {
                         } else {
                             println!("  mem_bytes      : (sin datos)");
                         }
+                        if let Some(depth) = w.queue_depth {
+                            println!("  queue_depth    : {}", depth);
+                        } else {
+                            println!("  queue_depth    : (sin datos)");
+                        }
                         println!();
                     }
                 }
@@ -279,6 +635,59 @@ pub async fn run() -> Result<()> {
             }
         }
 
+        Commands::Background => {
+            let url = format!("{}/api/v1/background", base_url);
+            let resp = client.get(&url).send().await?;
+            if resp.status().is_success() {
+                let workers: Vec<BackgroundWorkerInfo> = resp.json().await?;
+                if workers.is_empty() {
+                    println!("No hay workers en segundo plano registrados.");
+                } else {
+                    for w in workers {
+                        println!("{:<20} estado={:<6} last_tick={}s ago", w.name, w.state, w.last_tick_secs_ago);
+                        if let Some(err) = w.last_error {
+                            println!("  last_error: {}", err);
+                        }
+                    }
+                }
+            } else {
+                println!(
+                    "Error consultando /api/v1/background (status {})",
+                    resp.status()
+                );
+            }
+        }
+
+        Commands::BackgroundPause { name } => {
+            let url = format!("{}/api/v1/background/{}/pause", base_url, name);
+            let resp = client.post(&url).send().await?;
+            if resp.status().is_success() {
+                println!("Worker en segundo plano {} pausado", name);
+            } else {
+                println!("Error pausando worker {} (status {})", name, resp.status());
+            }
+        }
+
+        Commands::BackgroundResume { name } => {
+            let url = format!("{}/api/v1/background/{}/resume", base_url, name);
+            let resp = client.post(&url).send().await?;
+            if resp.status().is_success() {
+                println!("Worker en segundo plano {} reanudado", name);
+            } else {
+                println!("Error reanudando worker {} (status {})", name, resp.status());
+            }
+        }
+
+        Commands::BackgroundCancel { name } => {
+            let url = format!("{}/api/v1/background/{}/cancel", base_url, name);
+            let resp = client.post(&url).send().await?;
+            if resp.status().is_success() {
+                println!("Worker en segundo plano {} cancelado", name);
+            } else {
+                println!("Error cancelando worker {} (status {})", name, resp.status());
+            }
+        }
+
     }
 
     Ok(())
@@ -0,0 +1,201 @@
+use anyhow::Result;
+use common::{JobInfo, JobStatus, WorkerMetrics};
+use crossterm::event::{self, Event as CEvent, KeyCode, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Gauge, Row, Table};
+use ratatui::{Frame, Terminal};
+use reqwest::Client;
+use std::io;
+use std::time::Duration;
+
+/// Umbral de CPU a partir del cual pintamos la fila de un worker en rojo,
+/// igual al que usa el master para dejar de asignarle tareas nuevas
+/// (`MAX_WORKER_CPU_PERCENT` en `sparkmini-master`).
+const SATURATED_CPU_PERCENT: f32 = 90.0;
+
+/// Arranca el dashboard en vivo: alterna entre pantalla completa, pollea
+/// `/api/v1/workers` y `/api/v1/jobs` cada `interval` y redibuja, hasta
+/// que el usuario aprieta `q` o Ctrl-C.
+pub async fn run(base_url: String, interval: Duration) -> Result<()> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let result = run_loop(&mut terminal, base_url, interval).await;
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    result
+}
+
+async fn run_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    base_url: String,
+    interval: Duration,
+) -> Result<()> {
+    let client = Client::new();
+
+    loop {
+        let workers = fetch_workers(&client, &base_url).await.unwrap_or_default();
+        let jobs = fetch_jobs(&client, &base_url).await.unwrap_or_default();
+
+        terminal.draw(|f| draw(f, &workers, &jobs))?;
+
+        if event::poll(interval)? {
+            if let CEvent::Key(key) = event::read()? {
+                let quit = key.code == KeyCode::Char('q')
+                    || (key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL));
+                if quit {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+async fn fetch_workers(client: &Client, base_url: &str) -> Result<Vec<WorkerMetrics>> {
+    let url = format!("{}/api/v1/workers", base_url);
+    let workers = client.get(&url).send().await?.json().await?;
+    Ok(workers)
+}
+
+async fn fetch_jobs(client: &Client, base_url: &str) -> Result<Vec<JobInfo>> {
+    let url = format!("{}/api/v1/jobs", base_url);
+    let jobs = client.get(&url).send().await?.json().await?;
+    Ok(jobs)
+}
+
+fn draw(f: &mut Frame, workers: &[WorkerMetrics], jobs: &[JobInfo]) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(f.size());
+
+    draw_workers_table(f, chunks[0], workers);
+    draw_jobs_panel(f, chunks[1], jobs);
+}
+
+fn draw_workers_table(f: &mut Frame, area: Rect, workers: &[WorkerMetrics]) {
+    let header = Row::new(vec![
+        "host", "estado", "activas", "started", "ok", "failed", "avg_ms", "cpu%", "mem",
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows: Vec<Row> = workers
+        .iter()
+        .map(|w| {
+            let saturated = w.cpu_percent.map(|c| c > SATURATED_CPU_PERCENT).unwrap_or(false);
+            let style = if w.dead || saturated {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+
+            let estado = match w.state {
+                common::WorkerActivityState::Dead => "DEAD",
+                common::WorkerActivityState::Active => "activo",
+                common::WorkerActivityState::Idle => "idle",
+            };
+            let avg_ms = w
+                .avg_task_ms
+                .map(|v| format!("{:.0}", v))
+                .unwrap_or_else(|| "-".to_string());
+            let cpu = w
+                .cpu_percent
+                .map(|v| format!("{:.0}", v))
+                .unwrap_or_else(|| "-".to_string());
+            let mem = w
+                .mem_bytes
+                .map(|v| format!("{}MB", v / 1024 / 1024))
+                .unwrap_or_else(|| "-".to_string());
+
+            Row::new(vec![
+                Cell::from(w.hostname.clone()),
+                Cell::from(estado),
+                Cell::from(w.active_tasks.to_string()),
+                Cell::from(w.tasks_started.to_string()),
+                Cell::from(w.tasks_succeeded.to_string()),
+                Cell::from(w.tasks_failed.to_string()),
+                Cell::from(avg_ms),
+                Cell::from(cpu),
+                Cell::from(mem),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Percentage(20),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+    ];
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title("Workers"));
+
+    f.render_widget(table, area);
+}
+
+fn draw_jobs_panel(f: &mut Frame, area: Rect, jobs: &[JobInfo]) {
+    let block = Block::default().borders(Borders::ALL).title("Jobs");
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    if jobs.is_empty() {
+        let line = Line::from(Span::raw("No hay jobs todavía."));
+        f.render_widget(ratatui::widgets::Paragraph::new(line), inner);
+        return;
+    }
+
+    let row_constraints: Vec<Constraint> = jobs.iter().map(|_| Constraint::Length(1)).collect();
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(row_constraints)
+        .split(inner);
+
+    for (job, row) in jobs.iter().zip(rows.iter()) {
+        let done = job.completed_tasks + job.failed_tasks;
+        let ratio = if job.total_tasks > 0 {
+            (job.progress as f64).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        let color = match job.status {
+            JobStatus::Failed => Color::Red,
+            JobStatus::Succeeded => Color::Green,
+            _ => Color::Yellow,
+        };
+
+        let label = format!(
+            "{} [{:?}] {}/{}",
+            job.name, job.status, done, job.total_tasks
+        );
+
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(color))
+            .label(label)
+            .ratio(ratio);
+
+        f.render_widget(gauge, *row);
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dag {
@@ -26,4 +27,68 @@ pub struct DagNode {
 
     /// Campo clave para reduce_by_key o join, ej: "token".
     pub key: Option<String>,
+
+    /// Código fuente Lua de la UDF de este nodo (map/filter/flat_map/
+    /// reduce_by_key). Si viene presente, tiene prioridad sobre `fn_name`:
+    /// el engine compila este chunk una sola vez por tarea y lo llama por
+    /// registro (ver `engine::PipelineState`/`engine::LuaUdf`).
+    #[serde(default)]
+    pub fn_src: Option<String>,
+}
+
+/// Orden topológico de `dag.nodes` a partir de `dag.edges` (Kahn).
+/// Si queda algún nodo sin visitar (está en un ciclo, depende de uno, o
+/// tiene una arista desde un id que no está en `dag.nodes`), se agrega al
+/// final en el orden en que aparece, para no perder ningún nodo.
+pub fn topo_sort(dag: &Dag) -> Vec<String> {
+    let mut indegree: HashMap<&str, u32> = HashMap::new();
+    let mut adj: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for n in &dag.nodes {
+        indegree.entry(n.id.as_str()).or_insert(0);
+        adj.entry(n.id.as_str()).or_insert_with(Vec::new);
+    }
+
+    for (from, to) in &dag.edges {
+        *indegree.entry(to.as_str()).or_insert(0) += 1;
+        adj.entry(from.as_str()).or_insert_with(Vec::new).push(to.as_str());
+    }
+
+    let mut queue: VecDeque<&str> = dag
+        .nodes
+        .iter()
+        .map(|n| n.id.as_str())
+        .filter(|id| indegree.get(id).copied().unwrap_or(0) == 0)
+        .collect();
+
+    let mut order: Vec<String> = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+
+    while let Some(id) = queue.pop_front() {
+        if !seen.insert(id) {
+            continue;
+        }
+        order.push(id.to_string());
+
+        if let Some(children) = adj.get(id) {
+            for child in children {
+                if let Some(d) = indegree.get_mut(child) {
+                    if *d > 0 {
+                        *d -= 1;
+                    }
+                    if *d == 0 {
+                        queue.push_back(child);
+                    }
+                }
+            }
+        }
+    }
+
+    for n in &dag.nodes {
+        if !seen.contains(n.id.as_str()) {
+            order.push(n.id.clone());
+        }
+    }
+
+    order
 }
@@ -1,13 +1,19 @@
+use glob::Pattern;
+use mlua::LuaSerdeExt;
 use serde_json::{json, Value};
+use unicode_normalization::UnicodeNormalization;
 use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
+    cmp::{Ordering, Reverse},
+    collections::{hash_map::DefaultHasher, BinaryHeap, HashMap, HashSet},
     fs::{self, File},
     hash::{Hash, Hasher},
     io::{self, BufRead, BufReader, BufWriter, Write},
-    path::Path,
+    path::{Path, PathBuf},
+    thread,
 };
 
-use crate::dag::Dag;
+use crate::dag::{Dag, DagNode};
+use crate::jsonpath::{self, get_selector};
 
 /// Tipo genérico de registro (fila de datos).
 /// Usamos JSON para poder representar texto, CSV, JSONL, etc.
@@ -21,6 +27,115 @@ pub type Records = Vec<Record>;
 pub struct Partition {
     pub id: u32,
     pub path: String,
+    pub format: PartitionFormat,
+}
+
+/// Formato físico en que se guarda una partición intermedia en disco.
+///
+/// `Jsonl` es el formato histórico (una línea de JSON por registro) y es
+/// el que se sigue usando para lo que el usuario termina leyendo
+/// (`output_path`). `Binary` es para lo que sólo viaja entre stages
+/// (particiones de shuffle, spills de `SpillingAggregator`): cada
+/// registro va precedido por su longitud en varint y serializado con
+/// MessagePack (`rmp-serde`), así `read_partition` puede leerlo en
+/// streaming sin pasar por texto UTF-8, y los números no se degradan a
+/// strings en el viaje.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionFormat {
+    Jsonl,
+    Binary,
+}
+
+/// Escribe un `u64` como varint LEB128 (7 bits de datos por byte, bit
+/// alto como "sigue otro byte").
+fn write_varint<W: Write>(w: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+        w.write_all(&[byte])?;
+        if value == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Lee un varint LEB128, o `None` si el stream terminó exactamente antes
+/// de empezar uno nuevo (fin normal de archivo).
+fn read_varint<R: BufRead>(r: &mut R) -> io::Result<Option<u64>> {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+    let mut first = true;
+
+    loop {
+        let mut byte = [0u8; 1];
+        let n = r.read(&mut byte)?;
+        if n == 0 {
+            if first {
+                return Ok(None);
+            }
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "varint truncado a mitad de registro",
+            ));
+        }
+        first = false;
+
+        result |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+
+    Ok(Some(result))
+}
+
+/// Serializa `value` con MessagePack y lo escribe precedido por su
+/// longitud (varint), para poder leerlo de vuelta en streaming. Usado
+/// tanto por las particiones en `PartitionFormat::Binary` como por los
+/// spills de `SpillingAggregator`.
+fn write_length_prefixed<T: serde::Serialize, W: Write>(w: &mut W, value: &T) -> io::Result<()> {
+    let bytes = rmp_serde::to_vec(value)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    write_varint(w, bytes.len() as u64)?;
+    w.write_all(&bytes)
+}
+
+/// Lee de vuelta un valor escrito por `write_length_prefixed`, o `None`
+/// si no queda ningún registro más.
+fn read_length_prefixed<T: serde::de::DeserializeOwned, R: BufRead>(
+    r: &mut R,
+) -> io::Result<Option<T>> {
+    match read_varint(r)? {
+        None => Ok(None),
+        Some(len) => {
+            let mut buf = vec![0u8; len as usize];
+            r.read_exact(&mut buf)?;
+            let value: T = rmp_serde::from_slice(&buf)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+            Ok(Some(value))
+        }
+    }
+}
+
+/// Escribe un registro en el writer de una partición, en el formato que
+/// corresponda.
+fn write_partition_record<W: Write>(
+    w: &mut W,
+    format: PartitionFormat,
+    rec: &Record,
+) -> io::Result<()> {
+    match format {
+        PartitionFormat::Jsonl => {
+            serde_json::to_writer(&mut *w, rec)?;
+            w.write_all(b"\n")
+        }
+        PartitionFormat::Binary => write_length_prefixed(w, rec),
+    }
 }
 
 const DEFAULT_MAX_IN_MEM_KEYS: usize = 100_000;
@@ -34,9 +149,294 @@ fn max_in_mem_keys() -> usize {
         .unwrap_or(DEFAULT_MAX_IN_MEM_KEYS)
 }
 
-/// Acumulador clave→valor con spill a disco cuando el mapa crece demasiado.
+/// Concurrencia máxima para los executors paralelos de reduce/join por
+/// partición (ver `reduce_partitions_to_file_with`/`join_partitions_to_jsonl`):
+/// se puede fijar con la env var MINISPARK_MAX_CONCURRENCY; si no está
+/// seteada, se usa `std::thread::available_parallelism()` (o 1 si no se
+/// puede detectar).
+fn max_concurrency() -> usize {
+    std::env::var("MINISPARK_MAX_CONCURRENCY")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or_else(|| {
+            thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        })
+}
+
+/// Escape hatch para volver al camino secuencial de toda la vida (útil
+/// para debuggear): con MINISPARK_SEQUENTIAL=1 los executors paralelos
+/// de reduce/join corren con un único worker, ignorando
+/// `MINISPARK_MAX_CONCURRENCY`.
+fn sequential_mode() -> bool {
+    std::env::var("MINISPARK_SEQUENTIAL")
+        .map(|v| v == "1")
+        .unwrap_or(false)
+}
+
+/// Tamaño de grupo para repartir `len` ítems entre como mucho `workers`
+/// grupos contiguos lo más parejos posible (`ceil(len / workers)`),
+/// preservando el orden original -- así el merge final de los executors
+/// paralelos sólo necesita concatenar los grupos en ese mismo orden.
+fn chunk_size_for(len: usize, workers: usize) -> usize {
+    if len == 0 {
+        return 1;
+    }
+    let workers = workers.max(1);
+    (len + workers - 1) / workers
+}
+
+/// Concatena, en orden, el contenido de `inputs` en `output_path`: el
+/// paso de merge final de los executors paralelos de reduce/join. Como
+/// cada grupo de particiones es independiente de los demás (la misma
+/// clave no puede caer en dos grupos, porque ya vienen
+/// hash-particionadas por la misma clave), alcanza con pegar los
+/// archivos uno detrás del otro -- el resultado queda agrupado por
+/// partición en vez de ordenado globalmente por clave, pero es
+/// determinista porque los grupos siempre se concatenan en el mismo
+/// orden (el de las particiones de entrada).
+fn concat_files_in_order(inputs: &[PathBuf], output_path: &str) -> io::Result<()> {
+    if let Some(parent) = Path::new(output_path).parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    let mut writer = BufWriter::new(File::create(output_path)?);
+    for input in inputs {
+        let mut reader = BufReader::new(File::open(input)?);
+        io::copy(&mut reader, &mut writer)?;
+    }
+    writer.flush()
+}
+
+/// Acumulador parcial de una agregación en curso. Usamos `Value` para que
+/// cualquier `Aggregator` guarde la forma de acumulador que necesite (un
+/// número para sum/count, `{sum, count}` para avg, el extremo corriente
+/// para min/max, un array de vistos para count_distinct) sin que el
+/// resto de la máquina de spill/merge tenga que saber cuál es: con
+/// `Value` alcanza con que el `Aggregator` sepa interpretar su propia
+/// forma, y `write_length_prefixed` la serializa como MessagePack
+/// (`rmp_serde`) para los archivos de spill.
+pub type Acc = Value;
+
+/// Una agregación "mergeable": separa el acumulador *parcial* (lo que se
+/// puede ir sumando de a poco y mandar a un spill) del resultado *final*
+/// (lo que se calcula una sola vez, después de mergear todos los
+/// parciales). Así `avg` puede guardar `{sum, count}` en cada spill y
+/// dividir recién al final, en vez de promediar promedios.
+pub trait Aggregator {
+    /// Acumulador inicial a partir del primer valor visto para una clave.
+    fn init(&self, first: &Value) -> Acc;
+    /// Mezcla un valor nuevo dentro de un acumulador existente.
+    fn merge_value(&self, acc: &mut Acc, v: &Value);
+    /// Mezcla dos acumuladores parciales (ej: el del mapa en memoria con
+    /// el de un spill, o dos spills entre sí).
+    fn merge_acc(&self, a: &mut Acc, b: Acc);
+    /// Convierte el acumulador parcial ya mergeado en el valor final.
+    fn finalize(&self, acc: Acc) -> Value;
+}
+
+fn value_as_f64(v: &Value) -> f64 {
+    v.as_f64().unwrap_or(0.0)
+}
+
+/// sum: sumatoria numérica del valor.
+struct SumAgg;
+impl Aggregator for SumAgg {
+    fn init(&self, first: &Value) -> Acc {
+        json!(value_as_f64(first))
+    }
+    fn merge_value(&self, acc: &mut Acc, v: &Value) {
+        *acc = json!(value_as_f64(acc) + value_as_f64(v));
+    }
+    fn merge_acc(&self, a: &mut Acc, b: Acc) {
+        *a = json!(value_as_f64(a) + value_as_f64(b));
+    }
+    fn finalize(&self, acc: Acc) -> Value {
+        let f = value_as_f64(&acc);
+        if f.fract() == 0.0 && f >= 0.0 {
+            json!(f as u64)
+        } else {
+            json!(f)
+        }
+    }
+}
+
+/// count: cantidad de registros vistos por clave, sin mirar el valor.
+struct CountAgg;
+impl Aggregator for CountAgg {
+    fn init(&self, _first: &Value) -> Acc {
+        json!(1_u64)
+    }
+    fn merge_value(&self, acc: &mut Acc, _v: &Value) {
+        *acc = json!(acc.as_u64().unwrap_or(0) + 1);
+    }
+    fn merge_acc(&self, a: &mut Acc, b: Acc) {
+        *a = json!(a.as_u64().unwrap_or(0) + b.as_u64().unwrap_or(0));
+    }
+    fn finalize(&self, acc: Acc) -> Value {
+        acc
+    }
+}
+
+/// avg: promedio numérico. El acumulador parcial es `{sum, count}`, no
+/// el promedio ya calculado, para poder mergear varios parciales sin
+/// perder precisión (promediar promedios daría un resultado distinto).
+struct AvgAgg;
+impl Aggregator for AvgAgg {
+    fn init(&self, first: &Value) -> Acc {
+        json!({ "sum": value_as_f64(first), "count": 1_u64 })
+    }
+    fn merge_value(&self, acc: &mut Acc, v: &Value) {
+        let sum = acc.get("sum").map(value_as_f64).unwrap_or(0.0) + value_as_f64(v);
+        let count = acc.get("count").and_then(|x| x.as_u64()).unwrap_or(0) + 1;
+        *acc = json!({ "sum": sum, "count": count });
+    }
+    fn merge_acc(&self, a: &mut Acc, b: Acc) {
+        let sum = a.get("sum").map(value_as_f64).unwrap_or(0.0) + b.get("sum").map(value_as_f64).unwrap_or(0.0);
+        let count = a.get("count").and_then(|x| x.as_u64()).unwrap_or(0)
+            + b.get("count").and_then(|x| x.as_u64()).unwrap_or(0);
+        *a = json!({ "sum": sum, "count": count });
+    }
+    fn finalize(&self, acc: Acc) -> Value {
+        let sum = acc.get("sum").map(value_as_f64).unwrap_or(0.0);
+        let count = acc.get("count").and_then(|x| x.as_u64()).unwrap_or(0);
+        json!(if count == 0 { 0.0 } else { sum / count as f64 })
+    }
+}
+
+/// min: mínimo numérico visto por clave.
+struct MinAgg;
+impl Aggregator for MinAgg {
+    fn init(&self, first: &Value) -> Acc {
+        first.clone()
+    }
+    fn merge_value(&self, acc: &mut Acc, v: &Value) {
+        if value_as_f64(v) < value_as_f64(acc) {
+            *acc = v.clone();
+        }
+    }
+    fn merge_acc(&self, a: &mut Acc, b: Acc) {
+        if value_as_f64(&b) < value_as_f64(a) {
+            *a = b;
+        }
+    }
+    fn finalize(&self, acc: Acc) -> Value {
+        acc
+    }
+}
+
+/// max: máximo numérico visto por clave.
+struct MaxAgg;
+impl Aggregator for MaxAgg {
+    fn init(&self, first: &Value) -> Acc {
+        first.clone()
+    }
+    fn merge_value(&self, acc: &mut Acc, v: &Value) {
+        if value_as_f64(v) > value_as_f64(acc) {
+            *acc = v.clone();
+        }
+    }
+    fn merge_acc(&self, a: &mut Acc, b: Acc) {
+        if value_as_f64(&b) > value_as_f64(a) {
+            *a = b;
+        }
+    }
+    fn finalize(&self, acc: Acc) -> Value {
+        acc
+    }
+}
+
+/// count_distinct: cantidad de valores *distintos* vistos por clave. El
+/// acumulador parcial guarda el set de valores ya vistos (serializado
+/// como array), no el conteo final, para poder mergearlo con otro set
+/// sin contar dos veces un valor que aparece en ambos.
+struct CountDistinctAgg;
+impl CountDistinctAgg {
+    fn to_set(acc: &Value) -> Vec<String> {
+        acc.as_array()
+            .map(|a| a.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+            .unwrap_or_default()
+    }
+}
+impl Aggregator for CountDistinctAgg {
+    fn init(&self, first: &Value) -> Acc {
+        json!([value_to_dedup_key(first)])
+    }
+    fn merge_value(&self, acc: &mut Acc, v: &Value) {
+        let mut set = Self::to_set(acc);
+        let key = value_to_dedup_key(v);
+        if !set.contains(&key) {
+            set.push(key);
+        }
+        *acc = json!(set);
+    }
+    fn merge_acc(&self, a: &mut Acc, b: Acc) {
+        let mut set = Self::to_set(a);
+        for key in Self::to_set(&b) {
+            if !set.contains(&key) {
+                set.push(key);
+            }
+        }
+        *a = json!(set);
+    }
+    fn finalize(&self, acc: Acc) -> Value {
+        json!(Self::to_set(&acc).len() as u64)
+    }
+}
+
+fn value_to_dedup_key(v: &Value) -> String {
+    v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string())
+}
+
+/// Resuelve un `Aggregator` a partir de su nombre (tal como viene en
+/// `DagNode.fn_name` para un nodo `reduce_by_key`), igual que
+/// `apply_named_map`/`apply_named_filter` resuelven sus UDFs por nombre.
+/// Cualquier nombre no reconocido cae a `sum`, que es el comportamiento
+/// histórico de este operador.
+pub fn aggregator_for(name: &str) -> Box<dyn Aggregator> {
+    match name {
+        "count" => Box::new(CountAgg),
+        "avg" | "average" => Box::new(AvgAgg),
+        "min" => Box::new(MinAgg),
+        "max" => Box::new(MaxAgg),
+        "count_distinct" => Box::new(CountDistinctAgg),
+        _ => Box::new(SumAgg),
+    }
+}
+
+/// Encierra un campo entre comillas (duplicando las que ya tenga adentro)
+/// si contiene el delimitador, una comilla o un salto de línea, siguiendo
+/// el mismo criterio de quoting que RFC 4180. Si no hace falta, lo deja
+/// tal cual para no ensuciar la salida con comillas de más.
+fn csv_quote_field(s: &str, delimiter: char, quote: char) -> String {
+    if s.contains(delimiter) || s.contains(quote) || s.contains('\n') || s.contains('\r') {
+        let escaped = s.replace(quote, &format!("{quote}{quote}"));
+        format!("{quote}{escaped}{quote}")
+    } else {
+        s.to_string()
+    }
+}
+
+/// Formatea un `Value` final para una línea de CSV "clave,valor".
+fn format_value_for_csv(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Acumulador clave→valor con spill a disco cuando el mapa crece
+/// demasiado. El `Aggregator` define qué forma tiene cada acumulador
+/// parcial y cómo mergearlos; `SpillingAggregator` sólo se ocupa de
+/// cuándo escribir a disco y cómo volver a leer.
 struct SpillingAggregator {
-    map: HashMap<String, u64>,
+    agg: Box<dyn Aggregator>,
+    map: HashMap<String, Acc>,
     spill_files: Vec<String>,
     dir: String,
     threshold: usize,
@@ -44,11 +444,12 @@ struct SpillingAggregator {
 }
 
 impl SpillingAggregator {
-    fn new(dir: &str, threshold: usize) -> io::Result<Self> {
+    fn new(dir: &str, threshold: usize, agg: Box<dyn Aggregator>) -> io::Result<Self> {
         if !dir.is_empty() {
             fs::create_dir_all(dir)?;
         }
         Ok(Self {
+            agg,
             map: HashMap::new(),
             spill_files: Vec::new(),
             dir: dir.to_string(),
@@ -57,8 +458,31 @@ impl SpillingAggregator {
         })
     }
 
-    fn add(&mut self, key: &str, value: u64) -> io::Result<()> {
-        *self.map.entry(key.to_string()).or_insert(0) += value;
+    fn add(&mut self, key: &str, value: &Value) -> io::Result<()> {
+        match self.map.get_mut(key) {
+            Some(acc) => self.agg.merge_value(acc, value),
+            None => {
+                let acc = self.agg.init(value);
+                self.map.insert(key.to_string(), acc);
+            }
+        }
+        if self.map.len() >= self.threshold {
+            self.spill_one()?;
+        }
+        Ok(())
+    }
+
+    /// Igual que `add`, pero para cuando `value` ya es un acumulador
+    /// parcial (no un valor crudo) -- por ejemplo, cuando viene de una
+    /// partición que ya pasó por el combiner de `shuffle_to_partitions_with`.
+    /// Mergea con `merge_acc` en vez de inicializar/`merge_value`.
+    fn add_acc(&mut self, key: &str, acc: Acc) -> io::Result<()> {
+        match self.map.get_mut(key) {
+            Some(existing) => self.agg.merge_acc(existing, acc),
+            None => {
+                self.map.insert(key.to_string(), acc);
+            }
+        }
         if self.map.len() >= self.threshold {
             self.spill_one()?;
         }
@@ -73,19 +497,26 @@ impl SpillingAggregator {
         self.spill_counter += 1;
         // ← Hacemos el nombre de spill único por proceso + contador
         let pid = std::process::id();
-        let filename = format!("spill-{}-{}.jsonl", pid, self.spill_counter);
+        let filename = format!("spill-{}-{}.bin", pid, self.spill_counter);
         let path = Path::new(&self.dir).join(filename);
         let mut writer = BufWriter::new(File::create(&path)?);
 
-        for (k, v) in self.map.drain() {
-            let obj = json!({ "k": k, "v": v });
-            serde_json::to_writer(&mut writer, &obj).map_err(|e| {
+        // Ordenamos por clave antes de escribir para que cada spill sea un
+        // "run" ya ordenado: así `finalize_to_csv` puede mergearlos con un
+        // k-way merge en streaming en vez de releerlos enteros a memoria.
+        // El spill en sí se guarda en binario (ver `write_length_prefixed`):
+        // nunca lo ve el usuario, así que no hace falta pagar el costo de
+        // texto/JSON para algo que sólo viaja entre stages.
+        let mut entries: Vec<(String, Acc)> = self.map.drain().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for entry in &entries {
+            write_length_prefixed(&mut writer, entry).map_err(|e| {
                 io::Error::new(
                     io::ErrorKind::Other,
                     format!("error al escribir spill en {}: {e}", path.display()),
                 )
             })?;
-            writer.write_all(b"\n")?;
         }
 
         writer.flush()?;
@@ -95,35 +526,28 @@ impl SpillingAggregator {
     }
 
     fn finalize_to_csv(mut self, output_path: &str) -> io::Result<()> {
-        // Combinar mapa en memoria + spills en un acumulador final.
-        let mut final_acc: HashMap<String, u64> = HashMap::new();
-
-        for (k, v) in self.map.drain() {
-            *final_acc.entry(k).or_insert(0) += v;
+        // Cada spill es un run ya ordenado por clave; el mapa en memoria que
+        // quede (si no llegó a spillear) se ordena una vez y se trata como
+        // un run más. `finalize_to_csv` entonces hace un k-way merge en
+        // streaming sobre todos los runs, así la memoria residente es
+        // O(cantidad de runs) y no O(claves distintas).
+        let mut runs: Vec<Run> = Vec::with_capacity(self.spill_files.len() + 1);
+
+        if !self.map.is_empty() {
+            let mut entries: Vec<(String, Acc)> = self.map.drain().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            runs.push(Run::Memory {
+                entries,
+                pos: 0,
+            });
         }
 
         for spill_path in &self.spill_files {
             let file = File::open(spill_path)?;
-            let reader = BufReader::new(file);
-            for line in reader.lines() {
-                let line = line?;
-                if line.trim().is_empty() {
-                    continue;
-                }
-                let v: Value = serde_json::from_str(&line).map_err(|e| {
-                    io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        format!("error al parsear spill {}: {e}", spill_path),
-                    )
-                })?;
-                let key = v
-                    .get("k")
-                    .and_then(|x| x.as_str())
-                    .unwrap_or("")
-                    .to_string();
-                let val = v.get("v").and_then(|x| x.as_u64()).unwrap_or(0);
-                *final_acc.entry(key).or_insert(0) += val;
-            }
+            runs.push(Run::File {
+                reader: BufReader::new(file),
+                path: spill_path.clone(),
+            });
         }
 
         if let Some(parent) = Path::new(output_path).parent() {
@@ -135,12 +559,63 @@ impl SpillingAggregator {
         let out = File::create(output_path)?;
         let mut writer = BufWriter::new(out);
 
-        let mut entries: Vec<(String, u64)> = final_acc.into_iter().collect();
-        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        // Heap de (clave, índice de run); al sacar el mínimo, reponemos con
+        // la siguiente entrada de ese mismo run.
+        let mut heap: BinaryHeap<Reverse<(String, usize)>> = BinaryHeap::new();
+        let mut pending: Vec<Option<(String, Acc)>> = Vec::with_capacity(runs.len());
+
+        for (idx, run) in runs.iter_mut().enumerate() {
+            let next = run.next_entry()?;
+            pending.push(None);
+            if let Some((k, _)) = &next {
+                heap.push(Reverse((k.clone(), idx)));
+            }
+            pending[idx] = next;
+        }
+
+        let mut current_key: Option<String> = None;
+        let mut current_acc: Option<Acc> = None;
+
+        while let Some(Reverse((key, idx))) = heap.pop() {
+            let (_, acc) = pending[idx]
+                .take()
+                .expect("heap solo referencia runs con entrada pendiente");
+
+            match (&current_key, &mut current_acc) {
+                (Some(ck), Some(ca)) if *ck == key => {
+                    self.agg.merge_acc(ca, acc);
+                }
+                _ => {
+                    if let (Some(ck), Some(ca)) = (current_key.take(), current_acc.take()) {
+                        let val = self.agg.finalize(ca);
+                        writeln!(
+                            writer,
+                            "{},{}",
+                            csv_quote_field(&ck, ',', '"'),
+                            csv_quote_field(&format_value_for_csv(&val), ',', '"')
+                        )?;
+                    }
+                    current_key = Some(key);
+                    current_acc = Some(acc);
+                }
+            }
 
-        // Igual que antes: líneas "token,count"
-        for (key, val) in entries {
-            writeln!(writer, "{},{}", key, val)?;
+            let next = runs[idx].next_entry()?;
+            if let Some((k, _)) = &next {
+                heap.push(Reverse((k.clone(), idx)));
+            }
+            pending[idx] = next;
+        }
+
+        // Flush del último grupo acumulado.
+        if let (Some(ck), Some(ca)) = (current_key, current_acc) {
+            let val = self.agg.finalize(ca);
+            writeln!(
+                writer,
+                "{},{}",
+                csv_quote_field(&ck, ',', '"'),
+                csv_quote_field(&format_value_for_csv(&val), ',', '"')
+            )?;
         }
 
         writer.flush()?;
@@ -148,6 +623,46 @@ impl SpillingAggregator {
     }
 }
 
+/// Un run ordenado por clave para el merge externo de `finalize_to_csv`:
+/// o bien el mapa en memoria (ya ordenado) que no llegó a spillear, o bien
+/// un spill en disco que se lee registro por registro con
+/// `read_length_prefixed`.
+enum Run {
+    Memory {
+        entries: Vec<(String, Acc)>,
+        pos: usize,
+    },
+    File {
+        reader: BufReader<File>,
+        path: String,
+    },
+}
+
+impl Run {
+    /// Devuelve la siguiente entrada `(clave, acumulador)` del run, o
+    /// `None` si ya se agotó.
+    fn next_entry(&mut self) -> io::Result<Option<(String, Acc)>> {
+        match self {
+            Run::Memory { entries, pos } => {
+                if *pos >= entries.len() {
+                    return Ok(None);
+                }
+                let entry = entries[*pos].clone();
+                *pos += 1;
+                Ok(Some(entry))
+            }
+            Run::File { reader, path } => {
+                read_length_prefixed::<(String, Acc), _>(reader).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("error al parsear spill {}: {e}", path),
+                    )
+                })
+            }
+        }
+    }
+}
+
 /* =========================
    Operadores genéricos
    ========================= */
@@ -191,26 +706,49 @@ where
 ///   - Agrupa por el campo `key_field` (ej: "token").
 ///   - Suma el campo numérico `value_field` (ej: "count").
 ///   - Devuelve registros de la forma `{ key_field: <clave>, value_field: <suma> }`.
+///
+/// Atajo de `op_reduce_by_key_with` con el aggregator `sum` (el
+/// comportamiento histórico de este operador).
 pub fn op_reduce_by_key(input: Records, key_field: &str, value_field: &str) -> Records {
-    let mut acc: HashMap<String, u64> = HashMap::new();
+    op_reduce_by_key_with(input, key_field, value_field, aggregator_for("sum").as_ref())
+}
+
+/// reduce_by_key genérico: igual que `op_reduce_by_key` pero con el
+/// `Aggregator` que le pases (sum/count/avg/min/max/count_distinct, ver
+/// `aggregator_for`), así el mismo agrupamiento por clave sirve para
+/// cualquier reducción, no sólo sumar.
+pub fn op_reduce_by_key_with(
+    input: Records,
+    key_field: &str,
+    value_field: &str,
+    agg: &dyn Aggregator,
+) -> Records {
+    let mut acc: HashMap<String, Acc> = HashMap::new();
+    let mut key_order: Vec<String> = Vec::new();
 
     for rec in input.into_iter() {
         if let Some(obj) = rec.as_object() {
             let key_opt = obj.get(key_field).and_then(|v| v.as_str());
-            let val_opt = obj.get(value_field).and_then(|v| v.as_u64());
+            let val_opt = obj.get(value_field);
 
             if let (Some(key), Some(val)) = (key_opt, val_opt) {
-                *acc.entry(key.to_string()).or_insert(0) += val;
+                match acc.get_mut(key) {
+                    Some(a) => agg.merge_value(a, val),
+                    None => {
+                        key_order.push(key.to_string());
+                        acc.insert(key.to_string(), agg.init(val));
+                    }
+                }
             }
         }
     }
 
     // determinista: ordenar por clave
-    let mut entries: Vec<(String, u64)> = acc.into_iter().collect();
-    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    key_order.sort();
 
-    entries
+    key_order
         .into_iter()
+        .filter_map(|k| acc.remove(&k).map(|a| (k, agg.finalize(a))))
         .map(|(k, v)| {
             json!({
                 key_field: k,
@@ -220,6 +758,219 @@ pub fn op_reduce_by_key(input: Records, key_field: &str, value_field: &str) -> R
         .collect()
 }
 
+/// distinct: deja pasar un solo registro por cada valor distinto de
+/// `key_field` (el primero visto, en el orden de `input`). Ordena por
+/// clave para que el resultado sea determinístico, igual que
+/// `op_reduce_by_key`.
+pub fn op_distinct(input: Records, key_field: &str) -> Records {
+    let mut seen: HashMap<String, Record> = HashMap::new();
+    let mut key_order: Vec<String> = Vec::new();
+
+    for rec in input.into_iter() {
+        let key_opt = rec
+            .as_object()
+            .and_then(|obj| obj.get(key_field))
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+
+        if let Some(key) = key_opt {
+            if !seen.contains_key(&key) {
+                key_order.push(key.clone());
+                seen.insert(key, rec);
+            }
+        }
+    }
+
+    key_order.sort();
+    key_order.into_iter().filter_map(|k| seen.remove(&k)).collect()
+}
+
+/// Sentido de orden para una clave de `op_sort_by`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Compara dos `Value` numéricamente si ambos lo son, o como texto en
+/// cualquier otro caso (misma representación que `format_value_for_csv`,
+/// para que "ordenado" coincida con lo que el usuario ve en el CSV).
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+        _ => format_value_for_csv(a).cmp(&format_value_for_csv(b)),
+    }
+}
+
+/// sort_by: ordena `input` de forma estable por una o más claves, cada
+/// una con su propio `SortOrder` (se usan en orden hasta que alguna
+/// desempate; si ninguna lo hace, se preserva el orden original de
+/// `input`, por ser un sort estable).
+pub fn op_sort_by(mut input: Records, fields: &[(&str, SortOrder)]) -> Records {
+    input.sort_by(|a, b| {
+        for (field, order) in fields {
+            let va = a.get(*field).cloned().unwrap_or(Value::Null);
+            let vb = b.get(*field).cloned().unwrap_or(Value::Null);
+            let cmp = compare_values(&va, &vb);
+            let cmp = match order {
+                SortOrder::Asc => cmp,
+                SortOrder::Desc => cmp.reverse(),
+            };
+            if cmp != Ordering::Equal {
+                return cmp;
+            }
+        }
+        Ordering::Equal
+    });
+    input
+}
+
+/// is_in: deja pasar sólo los registros cuyo campo `field` esté en
+/// `values` (comparando por la misma representación de texto que usa
+/// `format_value_for_csv`, así "1" y 1 matchean igual que en el resto
+/// del engine).
+pub fn op_is_in(input: Records, field: &str, values: &HashSet<String>) -> Records {
+    op_filter(input, |rec| {
+        rec.get(field)
+            .map(|v| values.contains(&format_value_for_csv(v)))
+            .unwrap_or(false)
+    })
+}
+
+/// Función de agregación de `op_aggregate_by_key`: versión simple en
+/// memoria (sin spill a disco, a diferencia de `Aggregator`/`aggregator_for`,
+/// pensada para particiones completas) para cuando hace falta calcular
+/// varias agregaciones distintas sobre varios campos en una sola pasada.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggFn {
+    Sum,
+    Count,
+    Min,
+    Max,
+    Avg,
+    CollectList,
+}
+
+/// Acumulador parcial de un `(field, AggFn)` de `op_aggregate_by_key`.
+enum FieldAcc {
+    Sum(f64),
+    Count(u64),
+    Min(Value),
+    Max(Value),
+    Avg { sum: f64, count: u64 },
+    CollectList(Vec<Value>),
+}
+
+impl FieldAcc {
+    fn init(kind: AggFn, first: &Value) -> Self {
+        match kind {
+            AggFn::Sum => FieldAcc::Sum(value_as_f64(first)),
+            AggFn::Count => FieldAcc::Count(1),
+            AggFn::Min => FieldAcc::Min(first.clone()),
+            AggFn::Max => FieldAcc::Max(first.clone()),
+            AggFn::Avg => FieldAcc::Avg { sum: value_as_f64(first), count: 1 },
+            AggFn::CollectList => FieldAcc::CollectList(vec![first.clone()]),
+        }
+    }
+
+    fn merge(&mut self, v: &Value) {
+        match self {
+            FieldAcc::Sum(sum) => *sum += value_as_f64(v),
+            FieldAcc::Count(count) => *count += 1,
+            FieldAcc::Min(min) => {
+                if value_as_f64(v) < value_as_f64(min) {
+                    *min = v.clone();
+                }
+            }
+            FieldAcc::Max(max) => {
+                if value_as_f64(v) > value_as_f64(max) {
+                    *max = v.clone();
+                }
+            }
+            FieldAcc::Avg { sum, count } => {
+                *sum += value_as_f64(v);
+                *count += 1;
+            }
+            FieldAcc::CollectList(list) => list.push(v.clone()),
+        }
+    }
+
+    fn finalize(self) -> Value {
+        match self {
+            FieldAcc::Sum(sum) => {
+                if sum.fract() == 0.0 && sum >= 0.0 {
+                    json!(sum as u64)
+                } else {
+                    json!(sum)
+                }
+            }
+            FieldAcc::Count(count) => json!(count),
+            FieldAcc::Min(min) => min,
+            FieldAcc::Max(max) => max,
+            FieldAcc::Avg { sum, count } => json!(if count == 0 { 0.0 } else { sum / count as f64 }),
+            FieldAcc::CollectList(list) => json!(list),
+        }
+    }
+}
+
+/// aggregate_by_key genérico: agrupa `input` por `key_field` y calcula,
+/// en una sola pasada, una agregación por cada `(field, AggFn)` de
+/// `specs` -- generaliza el `sum` fijo de `op_reduce_by_key` a cualquier
+/// combinación de funciones sobre cualquier cantidad de campos. Cada
+/// registro de salida tiene `key_field` más un campo por cada spec (con
+/// el mismo nombre que el campo de entrada). Ordena por clave para que
+/// el resultado sea determinístico, igual que `op_reduce_by_key`. Si dos
+/// specs apuntan al mismo campo (ej: sum y avg sobre "score"), el
+/// segundo pisa al primero en el registro de salida, porque ambos
+/// escriben bajo el mismo nombre de campo.
+pub fn op_aggregate_by_key(input: Records, key_field: &str, specs: &[(&str, AggFn)]) -> Records {
+    let mut state: HashMap<String, Vec<FieldAcc>> = HashMap::new();
+    let mut key_order: Vec<String> = Vec::new();
+
+    for rec in input.iter() {
+        let key_opt = rec
+            .as_object()
+            .and_then(|obj| obj.get(key_field))
+            .and_then(|v| v.as_str());
+
+        let Some(key) = key_opt else { continue };
+
+        match state.get_mut(key) {
+            Some(accs) => {
+                for (acc, (field, _)) in accs.iter_mut().zip(specs.iter()) {
+                    if let Some(v) = rec.get(*field) {
+                        acc.merge(v);
+                    }
+                }
+            }
+            None => {
+                let mut accs = Vec::with_capacity(specs.len());
+                for (field, kind) in specs {
+                    let v = rec.get(*field).cloned().unwrap_or(Value::Null);
+                    accs.push(FieldAcc::init(*kind, &v));
+                }
+                key_order.push(key.to_string());
+                state.insert(key.to_string(), accs);
+            }
+        }
+    }
+
+    key_order.sort();
+
+    key_order
+        .into_iter()
+        .filter_map(|k| state.remove(&k).map(|accs| (k, accs)))
+        .map(|(k, accs)| {
+            let mut obj = serde_json::Map::new();
+            obj.insert(key_field.to_string(), json!(k));
+            for ((field, _), acc) in specs.iter().zip(accs.into_iter()) {
+                obj.insert(field.to_string(), acc.finalize());
+            }
+            Value::Object(obj)
+        })
+        .collect()
+}
+
 /* =========================
    DEMO: WordCount usando operadores (map / flat_map / filter / reduce_by_key)
    ========================= */
@@ -292,10 +1043,17 @@ where
    ========================= */
 
 /// Fusiona dos registros JSON en uno solo.
-/// - el campo `key_field` se mantiene una sola vez
+/// - el campo de clave (`jsonpath::leaf_name(key_field)`) se mantiene una sola vez
 /// - si un campo existe en ambos lados, se respeta el del lado izquierdo
 ///   y el del derecho se guarda con prefijo `right_`.
+///
+/// `key_field` puede ser un nombre plano o un JSONPath; acá sólo se
+/// compara contra el nombre plano `jsonpath::leaf_name(key_field)`. Sólo
+/// el lado "nulo" que arma `null_side_record` trae la clave aplanada bajo
+/// ese nombre; un registro leído de los datos la conserva donde estaba
+/// (anidada, si `key_field` es un JSONPath).
 fn merge_records(left: &Record, right: &Record, key_field: &str) -> Record {
+    let key_name = jsonpath::leaf_name(key_field);
     let mut obj = serde_json::Map::new();
 
     if let Some(lobj) = left.as_object() {
@@ -306,7 +1064,7 @@ fn merge_records(left: &Record, right: &Record, key_field: &str) -> Record {
 
     if let Some(robj) = right.as_object() {
         for (k, v) in robj {
-            if k == key_field {
+            if *k == key_name {
                 // ya existe desde el lado izquierdo; lo dejamos tal cual
                 continue;
             }
@@ -322,26 +1080,138 @@ fn merge_records(left: &Record, right: &Record, key_field: &str) -> Record {
     Value::Object(obj)
 }
 
+/// Modo de join que usa `op_join_by_key_with` para decidir qué hacer con
+/// las filas que no tienen contraparte en el otro lado.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinType {
+    /// Sólo filas con clave presente en ambos lados (comportamiento histórico).
+    Inner,
+    /// Todas las filas de la izquierda; las sin match se completan con
+    /// campos de la derecha en `null`.
+    LeftOuter,
+    /// Todas las filas de la derecha; las sin match se completan con
+    /// campos de la izquierda en `null`.
+    RightOuter,
+    /// Unión de `LeftOuter` y `RightOuter`.
+    FullOuter,
+    /// Sólo la fila de la izquierda (sin fusionar), una vez por fila,
+    /// cuando existe al menos un match a la derecha.
+    LeftSemi,
+    /// Sólo la fila de la izquierda (sin fusionar), cuando NO existe
+    /// ningún match a la derecha.
+    LeftAnti,
+}
+
+/// Resuelve un nombre de join (igual que `aggregator_for` para los
+/// aggregators) al `JoinType` correspondiente. Nombres no reconocidos
+/// caen a `Inner`, que es el comportamiento histórico.
+pub fn join_type_for(name: &str) -> JoinType {
+    match name {
+        "left_outer" | "left" => JoinType::LeftOuter,
+        "right_outer" | "right" => JoinType::RightOuter,
+        "full_outer" | "full" => JoinType::FullOuter,
+        "left_semi" | "semi" => JoinType::LeftSemi,
+        "left_anti" | "anti" => JoinType::LeftAnti,
+        _ => JoinType::Inner,
+    }
+}
+
+/// Fabrica un registro "nulo" para el lado que falta en un outer join:
+/// pone a `null` cada campo de `fields`, y si `key_value` viene, también
+/// completa el campo de clave (necesario del lado izquierdo, ya que
+/// `merge_records` conserva el campo de clave de la izquierda tal cual;
+/// del lado derecho no hace falta porque `merge_records` lo ignora).
+/// `key_field` puede ser un nombre plano o un JSONPath: el campo se
+/// guarda siempre aplanado bajo `jsonpath::leaf_name(key_field)`.
+fn null_side_record(fields: &HashSet<String>, key_field: &str, key_value: Option<&str>) -> Record {
+    let mut obj = serde_json::Map::new();
+    if let Some(k) = key_value {
+        obj.insert(jsonpath::leaf_name(key_field), json!(k));
+    }
+    for f in fields {
+        obj.insert(f.clone(), Value::Null);
+    }
+    Value::Object(obj)
+}
+
 /// Inner join en memoria entre dos colecciones por el campo `key_field`.
 /// Si hay N registros a la izquierda y M a la derecha con la misma clave,
 /// se generan N*M registros combinados.
+///
+/// Atajo de `op_join_by_key_with` con `JoinType::Inner` (el comportamiento
+/// histórico de este operador).
 pub fn op_join_by_key(left: Records, right: Records, key_field: &str) -> Records {
-    // indexamos el lado derecho por clave
+    op_join_by_key_with(left, right, key_field, JoinType::Inner)
+}
+
+/// Join en memoria entre dos colecciones por `key_field`, con el modo
+/// indicado por `join_type` (ver `JoinType`). Igual que `op_join_by_key`
+/// para `Inner`; en los demás modos completa o filtra según corresponda:
+/// - `LeftOuter`/`FullOuter`: filas de la izquierda sin match se emiten
+///   fusionadas contra un lado derecho "nulo" (mismos campos, en `null`).
+/// - `RightOuter`/`FullOuter`: al terminar de recorrer la izquierda, las
+///   filas de la derecha que nunca matchearon se emiten fusionadas
+///   contra un lado izquierdo "nulo".
+/// - `LeftSemi`: una fila por cada fila de la izquierda que matchea, sin
+///   fusionar (útil para "existe" sin duplicar por N*M).
+/// - `LeftAnti`: una fila por cada fila de la izquierda que NO matchea.
+///
+/// `key_field` acepta nombre plano o JSONPath (ver `jsonpath::get_selector`);
+/// en el registro de salida la clave queda donde estaba en los datos
+/// (anidada, si es un JSONPath). Sólo las filas de la derecha sin match
+/// (`RightOuter`/`FullOuter`), fusionadas contra un izquierdo "nulo", la
+/// traen aplanada bajo `jsonpath::leaf_name(key_field)` (ver
+/// `null_side_record`).
+pub fn op_join_by_key_with(
+    left: Records,
+    right: Records,
+    key_field: &str,
+    join_type: JoinType,
+) -> Records {
+    // Indexamos el lado derecho por clave, guardando el orden de
+    // aparición de las claves (para emitir las filas sin match al final
+    // en un orden estable) y el conjunto de sus campos (para fabricar un
+    // lado derecho "nulo" con las mismas columnas cuando no hay match).
+    let key_name = jsonpath::leaf_name(key_field);
     let mut index: HashMap<String, Vec<Record>> = HashMap::new();
+    let mut right_key_order: Vec<String> = Vec::new();
+    let mut right_fields: HashSet<String> = HashSet::new();
 
     for rec in right.into_iter() {
         if let Some(obj) = rec.as_object() {
-            if let Some(k) = obj.get(key_field).and_then(|v| v.as_str()) {
-                index.entry(k.to_string()).or_default().push(rec);
+            for k in obj.keys() {
+                if *k != key_name {
+                    right_fields.insert(k.clone());
+                }
+            }
+        }
+
+        let key_opt = get_selector(&rec, key_field)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        if let Some(k) = key_opt {
+            if !index.contains_key(&k) {
+                right_key_order.push(k.clone());
             }
+            index.entry(k).or_default().push(rec);
         }
     }
 
     let mut out = Vec::new();
+    let mut left_fields: HashSet<String> = HashSet::new();
+    let mut matched_right_keys: HashSet<String> = HashSet::new();
 
     for lrec in left.into_iter() {
-        let key_opt = lrec
-            .get(key_field)
+        if let Some(obj) = lrec.as_object() {
+            for k in obj.keys() {
+                if *k != key_name {
+                    left_fields.insert(k.clone());
+                }
+            }
+        }
+
+        let key_opt = get_selector(&lrec, key_field)
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
 
@@ -349,11 +1219,63 @@ pub fn op_join_by_key(left: Records, right: Records, key_field: &str) -> Records
             continue;
         };
 
-        if let Some(r_matches) = index.get(&key) {
-            for rrec in r_matches {
-                out.push(merge_records(&lrec, rrec, key_field));
-            }
-        }
+        let r_matches = index.get(&key);
+
+        match join_type {
+            JoinType::Inner => {
+                if let Some(r_matches) = r_matches {
+                    for rrec in r_matches {
+                        out.push(merge_records(&lrec, rrec, key_field));
+                    }
+                }
+            }
+            JoinType::LeftOuter | JoinType::FullOuter => match r_matches {
+                Some(r_matches) => {
+                    matched_right_keys.insert(key.clone());
+                    for rrec in r_matches {
+                        out.push(merge_records(&lrec, rrec, key_field));
+                    }
+                }
+                None => {
+                    let right_null = null_side_record(&right_fields, key_field, None);
+                    out.push(merge_records(&lrec, &right_null, key_field));
+                }
+            },
+            JoinType::RightOuter => {
+                if let Some(r_matches) = r_matches {
+                    matched_right_keys.insert(key.clone());
+                    for rrec in r_matches {
+                        out.push(merge_records(&lrec, rrec, key_field));
+                    }
+                }
+                // Sin match: no emitimos nada por la izquierda; las filas
+                // de la derecha sin match se agregan al final.
+            }
+            JoinType::LeftSemi => {
+                if r_matches.is_some() {
+                    out.push(lrec);
+                }
+            }
+            JoinType::LeftAnti => {
+                if r_matches.is_none() {
+                    out.push(lrec);
+                }
+            }
+        }
+    }
+
+    if matches!(join_type, JoinType::RightOuter | JoinType::FullOuter) {
+        for key in &right_key_order {
+            if matched_right_keys.contains(key) {
+                continue;
+            }
+            if let Some(r_matches) = index.get(key) {
+                let left_null = null_side_record(&left_fields, key_field, Some(key));
+                for rrec in r_matches {
+                    out.push(merge_records(&left_null, rrec, key_field));
+                }
+            }
+        }
     }
 
     out
@@ -363,41 +1285,137 @@ pub fn op_join_by_key(left: Records, right: Records, key_field: &str) -> Records
    Lectura de archivos a Records
    ========================= */
 
-pub fn read_csv_to_records(path: &str) -> io::Result<Records> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let mut out = Vec::new();
+/// Opciones de lectura de CSV: delimitador/comilla configurables e
+/// inferencia de tipos opcional (desactivada por default para no romper
+/// a quien ya asume que todo valor de CSV llega como string JSON).
+#[derive(Debug, Clone)]
+pub struct CsvReadOptions {
+    pub delimiter: char,
+    pub quote: char,
+    pub infer_types: bool,
+}
 
-    // asumiendo primera línea = encabezados
-    let mut lines = reader.lines();
+impl Default for CsvReadOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote: '"',
+            infer_types: false,
+        }
+    }
+}
 
-    let header_line = match lines.next() {
-        Some(l) => l?,
-        None => return Ok(out),
-    };
+/// Tokeniza contenido CSV en filas de campos, respetando `RFC 4180`:
+/// campos entre comillas pueden contener el delimitador, comillas
+/// escapadas como `""` y saltos de línea. Un campo no-quoteado se
+/// trimea (comportamiento histórico de este reader); uno quoteado se
+/// conserva tal cual vino.
+fn parse_csv_rows(content: &str, delimiter: char, quote: char) -> Vec<Vec<String>> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut field_quoted = false;
+    let mut row_has_content = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == quote {
+                if chars.peek() == Some(&quote) {
+                    field.push(quote);
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+
+        if c == quote && field.is_empty() && !field_quoted {
+            in_quotes = true;
+            field_quoted = true;
+            row_has_content = true;
+        } else if c == delimiter {
+            let val = if field_quoted { field.clone() } else { field.trim().to_string() };
+            row.push(val);
+            field.clear();
+            field_quoted = false;
+            row_has_content = true;
+        } else if c == '\r' {
+            // ignorado; el fin de línea real lo marca '\n'
+        } else if c == '\n' {
+            let val = if field_quoted { field.clone() } else { field.trim().to_string() };
+            row.push(val);
+            field.clear();
+            field_quoted = false;
+            rows.push(std::mem::take(&mut row));
+            row_has_content = false;
+        } else {
+            field.push(c);
+            row_has_content = true;
+        }
+    }
+
+    // Última fila, si el archivo no termina con un salto de línea.
+    if row_has_content || !field.is_empty() || !row.is_empty() {
+        let val = if field_quoted { field } else { field.trim().to_string() };
+        row.push(val);
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// Intento best-effort de inferir el tipo de un valor de CSV: entero,
+/// de punto flotante o, si no parsea como número, el string tal cual.
+fn infer_csv_value(raw: &str) -> Value {
+    if let Ok(i) = raw.parse::<i64>() {
+        return json!(i);
+    }
+    if let Ok(f) = raw.parse::<f64>() {
+        return json!(f);
+    }
+    json!(raw)
+}
+
+pub fn read_csv_to_records(path: &str) -> io::Result<Records> {
+    read_csv_to_records_with(path, &CsvReadOptions::default())
+}
 
+/// Igual que `read_csv_to_records` pero con delimitador/comilla propios
+/// y, opcionalmente, inferencia de tipos numéricos.
+pub fn read_csv_to_records_with(path: &str, opts: &CsvReadOptions) -> io::Result<Records> {
+    let content = fs::read_to_string(path)?;
     // Limpia BOM por si viene de Excel/Windows
-    let header_line = header_line.trim_start_matches('\u{feff}');
+    let content = content.trim_start_matches('\u{feff}');
 
-    let headers: Vec<String> = header_line
-        .split(',')
-        .map(|s| s.trim().trim_start_matches('\u{feff}').to_string())
+    let mut rows = parse_csv_rows(content, opts.delimiter, opts.quote).into_iter();
+    let mut out = Vec::new();
+
+    let header_row = match rows.next() {
+        Some(r) => r,
+        None => return Ok(out),
+    };
+    let headers: Vec<String> = header_row
+        .iter()
+        .map(|h| h.trim_start_matches('\u{feff}').to_string())
         .collect();
 
-    for line_res in lines {
-        let line = line_res?;
-        if line.trim().is_empty() {
+    for row in rows {
+        // Fila en blanco entre registros (ver `parse_csv_rows`): se salta,
+        // igual que antes.
+        if row.len() == 1 && row[0].is_empty() {
             continue;
         }
 
-        let cols: Vec<&str> = line.split(',').collect();
         let mut obj = serde_json::Map::new();
-
         for (idx, h) in headers.iter().enumerate() {
-            let mut val = cols.get(idx).unwrap_or(&"").trim();
-            // Por si algún valor viene con BOM
-            val = val.trim_start_matches('\u{feff}');
-            obj.insert(h.clone(), json!(val));
+            let raw = row.get(idx).map(|s| s.as_str()).unwrap_or("");
+            let val = if opts.infer_types { infer_csv_value(raw) } else { json!(raw) };
+            obj.insert(h.clone(), val);
         }
 
         out.push(Value::Object(obj));
@@ -427,9 +1445,112 @@ pub fn read_jsonl_to_records(path: &str) -> io::Result<Records> {
    WordCount en memoria (versión simple)
    ========================= */
 
+/// Forma de normalización Unicode a aplicar antes de tokenizar (ver
+/// `TokenizerConfig`). Sin esto, texto equivalente pero codificado
+/// distinto (tilde precompuesta vs. tilde combinante, por ejemplo)
+/// termina contado como tokens distintos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationForm {
+    /// Composición canónica: alcanza para el caso de "tilde combinante
+    /// vs. precompuesta". Es el default.
+    Nfc,
+    /// Composición canónica + folding de compatibilidad (ligaduras,
+    /// variantes de ancho, etc. caen al mismo token).
+    Nfkc,
+}
+
+/// Configuración del tokenizador por defecto (`DefaultTokenizer`) usado
+/// por WordCount: forma de normalización, stopwords a descartar y
+/// longitud mínima de token (en caracteres, no bytes).
+#[derive(Debug, Clone)]
+pub struct TokenizerConfig {
+    pub normalization: NormalizationForm,
+    pub stopwords: HashSet<String>,
+    pub min_len: usize,
+}
+
+impl Default for TokenizerConfig {
+    fn default() -> Self {
+        TokenizerConfig {
+            normalization: NormalizationForm::Nfc,
+            stopwords: HashSet::new(),
+            min_len: 1,
+        }
+    }
+}
+
+/// Política de tokenización de WordCount: separa un texto en tokens. Se
+/// puede pasar una implementación propia a las variantes `_with_tokenizer`
+/// de los pipelines de WordCount para cambiar normalización, stopwords o
+/// longitud mínima sin tocar el resto del pipeline.
+pub trait Tokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String>;
+}
+
+/// Tokenizador usado por defecto: normaliza Unicode según `config`,
+/// separa por espacios, descarta todo lo que no sea alfanumérico ni `_`,
+/// pasa a minúsculas y filtra stopwords/tokens demasiado cortos.
+pub struct DefaultTokenizer {
+    config: TokenizerConfig,
+}
+
+impl DefaultTokenizer {
+    pub fn new(config: TokenizerConfig) -> Self {
+        DefaultTokenizer { config }
+    }
+}
+
+impl Default for DefaultTokenizer {
+    fn default() -> Self {
+        DefaultTokenizer::new(TokenizerConfig::default())
+    }
+}
+
+impl Tokenizer for DefaultTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        let normalized: String = match self.config.normalization {
+            NormalizationForm::Nfc => text.nfc().collect(),
+            NormalizationForm::Nfkc => text.nfkc().collect(),
+        };
+
+        let mut out = Vec::new();
+        for raw in normalized.split_whitespace() {
+            let cleaned: String = raw
+                .chars()
+                .filter(|c| c.is_alphanumeric() || *c == '_')
+                .collect::<String>()
+                .to_lowercase();
+
+            if cleaned.is_empty() || cleaned.chars().count() < self.config.min_len {
+                continue;
+            }
+            if self.config.stopwords.contains(&cleaned) {
+                continue;
+            }
+
+            out.push(cleaned);
+        }
+
+        out
+    }
+}
+
 /// Etapa 1 de WordCount:
 ///   líneas -> registros { "token": <palabra_normalizada>, "count": 1 }
+///
+/// Atajo de `wc_stage1_make_token_records_with` con `DefaultTokenizer`
+/// (el comportamiento histórico de este pipeline).
 fn wc_stage1_make_token_records<I>(lines: I) -> Records
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    wc_stage1_make_token_records_with(lines, &DefaultTokenizer::default())
+}
+
+/// Igual que `wc_stage1_make_token_records`, pero con el `Tokenizer` que
+/// le pases (normalización, stopwords y longitud mínima configurables).
+fn wc_stage1_make_token_records_with<I>(lines: I, tokenizer: &dyn Tokenizer) -> Records
 where
     I: IntoIterator,
     I::Item: AsRef<str>,
@@ -437,20 +1558,11 @@ where
     let mut recs: Records = Vec::new();
 
     for line in lines {
-        let line = line.as_ref();
-        for raw in line.split_whitespace() {
-            let cleaned: String = raw
-                .chars()
-                .filter(|c| c.is_alphanumeric() || *c == '_')
-                .collect::<String>()
-                .to_lowercase();
-
-            if !cleaned.is_empty() {
-                recs.push(json!({
-                    "token": cleaned,
-                    "count": 1_u64,
-                }));
-            }
+        for token in tokenizer.tokenize(line.as_ref()) {
+            recs.push(json!({
+                "token": token,
+                "count": 1_u64,
+            }));
         }
     }
 
@@ -459,37 +1571,49 @@ where
 
 /// Pipeline completo de WordCount en memoria (sin particiones),
 /// usando la etapa 1 + reduce_by_key.
+///
+/// Atajo de `wordcount_from_lines_with_tokenizer` con `DefaultTokenizer`.
 pub fn wordcount_from_lines<I>(lines: I) -> Records
 where
     I: IntoIterator,
     I::Item: AsRef<str>,
 {
-    let recs = wc_stage1_make_token_records(lines);
+    wordcount_from_lines_with_tokenizer(lines, &DefaultTokenizer::default())
+}
+
+/// Igual que `wordcount_from_lines`, pero con el `Tokenizer` que le pases.
+pub fn wordcount_from_lines_with_tokenizer<I>(lines: I, tokenizer: &dyn Tokenizer) -> Records
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let recs = wc_stage1_make_token_records_with(lines, tokenizer);
     op_reduce_by_key(recs, "token", "count")
 }
 
 /// Etapa 1 de WordCount desde registros:
 ///   records con campo `text_field` -> registros { "token": <palabra>, "count": 1 }
+///
+/// `text_field` acepta tanto un nombre plano (`"text"`) como una
+/// expresión JSONPath (`"$.body.text"`) para registros anidados; ver
+/// `jsonpath::get_selector`.
+///
+/// Atajo de `wc_stage1_from_records_with` con `DefaultTokenizer`.
 fn wc_stage1_from_records(input: Records, text_field: &str) -> Records {
+    wc_stage1_from_records_with(input, text_field, &DefaultTokenizer::default())
+}
+
+/// Igual que `wc_stage1_from_records`, pero con el `Tokenizer` que le pases.
+fn wc_stage1_from_records_with(input: Records, text_field: &str, tokenizer: &dyn Tokenizer) -> Records {
     let mut recs: Records = Vec::new();
 
     for rec in input.into_iter() {
-        if let Some(obj) = rec.as_object() {
-            if let Some(text) = obj.get(text_field).and_then(|v| v.as_str()) {
-                for raw in text.split_whitespace() {
-                    let cleaned: String = raw
-                        .chars()
-                        .filter(|c| c.is_alphanumeric() || *c == '_')
-                        .collect::<String>()
-                        .to_lowercase();
-
-                    if !cleaned.is_empty() {
-                        recs.push(json!({
-                            "token": cleaned,
-                            "count": 1_u64,
-                        }));
-                    }
-                }
+        if let Some(text) = get_selector(&rec, text_field).and_then(|v| v.as_str()) {
+            for token in tokenizer.tokenize(text) {
+                recs.push(json!({
+                    "token": token,
+                    "count": 1_u64,
+                }));
             }
         }
     }
@@ -499,18 +1623,39 @@ fn wc_stage1_from_records(input: Records, text_field: &str) -> Records {
 
 /// WordCount para un archivo CSV.
 /// Se asume que el CSV tiene una columna `text_field` con el texto a tokenizar.
+///
+/// Atajo de `wordcount_csv_file_shuffled_local_with_tokenizer` con `DefaultTokenizer`.
 pub fn wordcount_csv_file_shuffled_local(
     input_path: &str,
     text_field: &str,
     tmp_dir: &str,
     num_partitions: u32,
     output_path: &str,
+) -> io::Result<()> {
+    wordcount_csv_file_shuffled_local_with_tokenizer(
+        input_path,
+        text_field,
+        tmp_dir,
+        num_partitions,
+        output_path,
+        &DefaultTokenizer::default(),
+    )
+}
+
+/// Igual que `wordcount_csv_file_shuffled_local`, pero con el `Tokenizer` que le pases.
+pub fn wordcount_csv_file_shuffled_local_with_tokenizer(
+    input_path: &str,
+    text_field: &str,
+    tmp_dir: &str,
+    num_partitions: u32,
+    output_path: &str,
+    tokenizer: &dyn Tokenizer,
 ) -> io::Result<()> {
     // 1) Leer registros desde CSV
     let recs = read_csv_to_records(input_path)?;
 
     // 2) Stage1: records -> tokens {token,count=1}
-    let token_records = wc_stage1_from_records(recs, text_field);
+    let token_records = wc_stage1_from_records_with(recs, text_field, tokenizer);
 
     // 3) Shuffle: token -> particiones por hash(token)
     //    Usamos un stage_id único por archivo para evitar colisiones entre tareas.
@@ -520,32 +1665,57 @@ pub fn wordcount_csv_file_shuffled_local(
         .unwrap_or("nofile");
     let stage_id = format!("wc_stage1_csv_{}", file_key);
 
-    let partitions = shuffle_to_partitions(
+    // Combine del lado del map: WordCount es sum-por-token, así que el
+    // shuffle ya manda acumuladores parciales en vez de un {token,count:1}
+    // por ocurrencia.
+    let partitions = shuffle_to_partitions_with(
         token_records,
         "token",
         num_partitions,
         tmp_dir,
         &stage_id,
+        Some(("count", "sum")),
     )?;
 
-    // 4) Reduce: sum(count) por token en todas las particiones
-    reduce_partitions_to_file(&partitions, "token", "count", output_path)
+    // 4) Reduce: sum(count) por token en todas las particiones (ya combinadas)
+    reduce_partitions_to_file_with(&partitions, "token", "count", output_path, "sum", true)
 }
 
 /// WordCount para un archivo JSONL.
 /// Se asume que cada línea es un objeto JSON con un campo `text_field` con el texto.
+///
+/// Atajo de `wordcount_jsonl_file_shuffled_local_with_tokenizer` con `DefaultTokenizer`.
 pub fn wordcount_jsonl_file_shuffled_local(
     input_path: &str,
     text_field: &str,
     tmp_dir: &str,
     num_partitions: u32,
     output_path: &str,
+) -> io::Result<()> {
+    wordcount_jsonl_file_shuffled_local_with_tokenizer(
+        input_path,
+        text_field,
+        tmp_dir,
+        num_partitions,
+        output_path,
+        &DefaultTokenizer::default(),
+    )
+}
+
+/// Igual que `wordcount_jsonl_file_shuffled_local`, pero con el `Tokenizer` que le pases.
+pub fn wordcount_jsonl_file_shuffled_local_with_tokenizer(
+    input_path: &str,
+    text_field: &str,
+    tmp_dir: &str,
+    num_partitions: u32,
+    output_path: &str,
+    tokenizer: &dyn Tokenizer,
 ) -> io::Result<()> {
     // 1) Leer registros desde JSONL
     let recs = read_jsonl_to_records(input_path)?;
 
     // 2) Stage1: records -> tokens {token,count=1}
-    let token_records = wc_stage1_from_records(recs, text_field);
+    let token_records = wc_stage1_from_records_with(recs, text_field, tokenizer);
 
     // 3) Shuffle: token -> particiones por hash(token)
     let file_key = Path::new(input_path)
@@ -554,16 +1724,18 @@ pub fn wordcount_jsonl_file_shuffled_local(
         .unwrap_or("nofile");
     let stage_id = format!("wc_stage1_jsonl_{}", file_key);
 
-    let partitions = shuffle_to_partitions(
+    // Combine del lado del map: ver wordcount_csv_file_shuffled_local.
+    let partitions = shuffle_to_partitions_with(
         token_records,
         "token",
         num_partitions,
         tmp_dir,
         &stage_id,
+        Some(("count", "sum")),
     )?;
 
-    // 4) Reduce: sum(count) por token en todas las particiones
-    reduce_partitions_to_file(&partitions, "token", "count", output_path)
+    // 4) Reduce: sum(count) por token en todas las particiones (ya combinadas)
+    reduce_partitions_to_file_with(&partitions, "token", "count", output_path, "sum", true)
 }
 
 /* =========================
@@ -578,45 +1750,132 @@ fn hash_key_to_partition(key: &str, num_partitions: u32) -> u32 {
 
 /// Hace un shuffle de registros a N particiones en disco, usando `key_field`:
 ///   - Crea carpeta: <base_dir>/<stage_id>/
-///   - Crea archivos JSONL: part-0.jsonl, part-1.jsonl, ...
+///   - Crea archivos binarios: part-0.bin, part-1.bin, ... (ver `PartitionFormat`).
 ///   - Cada registro se manda según hash(key) % num_partitions.
 /// Devuelve los metadatos de las particiones creadas.
+///
+/// Atajo de `shuffle_to_partitions_with` sin combine del lado del map
+/// (el comportamiento histórico de este operador).
 pub fn shuffle_to_partitions(
     input: Records,
     key_field: &str,
     num_partitions: u32,
     base_dir: &str,
     stage_id: &str,
+) -> io::Result<Vec<Partition>> {
+    shuffle_to_partitions_with(input, key_field, num_partitions, base_dir, stage_id, None)
+}
+
+/// Vuelca a disco las claves acumuladas en `map` para una partición,
+/// como registros `{key_field, value_field}`, y limpia el mapa.
+fn flush_combiner(
+    writer: &mut BufWriter<File>,
+    format: PartitionFormat,
+    key_field: &str,
+    value_field: &str,
+    map: &mut HashMap<String, Acc>,
+) -> io::Result<()> {
+    for (k, acc) in map.drain() {
+        let mut obj = serde_json::Map::new();
+        obj.insert(key_field.to_string(), json!(k));
+        obj.insert(value_field.to_string(), acc);
+        write_partition_record(writer, format, &Value::Object(obj))?;
+    }
+    Ok(())
+}
+
+/// Igual que `shuffle_to_partitions`, pero con combine del lado del map
+/// opcional: `combine = Some((value_field, agg_name))` activa un
+/// `HashMap<key, Acc>` *por partición* (ver `Aggregator`) que pliega los
+/// registros localmente y sólo vuelca `{key_field, value_field}` a disco
+/// cuando esa partición llega a `max_in_mem_keys()` claves o al terminar
+/// la entrada -- el resultado final del reduce no cambia (ver
+/// `reduce_partitions_to_file_with` con `combined = true`), pero para
+/// claves muy repetidas (como WordCount) el archivo de partición queda
+/// órdenes de magnitud más chico. Pipelines no agregables (joins,
+/// pass-through genérico) usan `combine: None` para no activarlo.
+pub fn shuffle_to_partitions_with(
+    input: Records,
+    key_field: &str,
+    num_partitions: u32,
+    base_dir: &str,
+    stage_id: &str,
+    combine: Option<(&str, &str)>,
 ) -> io::Result<Vec<Partition>> {
     let stage_dir = Path::new(base_dir).join(stage_id);
     fs::create_dir_all(&stage_dir)?;
 
+    // Las particiones de shuffle sólo viajan entre stages (nunca las ve
+    // el usuario final), así que se escriben en binario: ver
+    // `PartitionFormat`.
+    let format = PartitionFormat::Binary;
+    let ext = match format {
+        PartitionFormat::Jsonl => "jsonl",
+        PartitionFormat::Binary => "bin",
+    };
+
     // Abrimos un writer por partición
     let mut writers: Vec<BufWriter<File>> = Vec::new();
     let mut parts: Vec<Partition> = Vec::new();
 
     for pid in 0..num_partitions {
-        let path = stage_dir.join(format!("part-{}.jsonl", pid));
+        let path = stage_dir.join(format!("part-{}.{}", pid, ext));
         let file = File::create(&path)?;
         writers.push(BufWriter::new(file));
         parts.push(Partition {
             id: pid,
             path: path.to_string_lossy().to_string(),
+            format,
         });
     }
 
-    // Escribimos cada registro en la partición que le toca
-    for rec in input.into_iter() {
-        let key = rec
-            .get(key_field)
-            .and_then(|v| v.as_str())
-            .unwrap_or_default()
-            .to_string();
+    match combine {
+        None => {
+            // Escribimos cada registro en la partición que le toca
+            for rec in input.into_iter() {
+                let key = rec
+                    .get(key_field)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+
+                let pid = hash_key_to_partition(&key, num_partitions) as usize;
+
+                write_partition_record(&mut writers[pid], format, &rec)?;
+            }
+        }
+        Some((value_field, agg_name)) => {
+            let agg = aggregator_for(agg_name);
+            let threshold = max_in_mem_keys();
+            let mut combiners: Vec<HashMap<String, Acc>> =
+                (0..num_partitions).map(|_| HashMap::new()).collect();
+
+            for rec in input.into_iter() {
+                let key = rec
+                    .get(key_field)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                let value = rec.get(value_field).cloned().unwrap_or(Value::Null);
+                let pid = hash_key_to_partition(&key, num_partitions) as usize;
+
+                match combiners[pid].get_mut(&key) {
+                    Some(acc) => agg.merge_value(acc, &value),
+                    None => {
+                        let acc = agg.init(&value);
+                        combiners[pid].insert(key, acc);
+                    }
+                }
 
-        let pid = hash_key_to_partition(&key, num_partitions) as usize;
+                if combiners[pid].len() >= threshold {
+                    flush_combiner(&mut writers[pid], format, key_field, value_field, &mut combiners[pid])?;
+                }
+            }
 
-        serde_json::to_writer(&mut writers[pid], &rec)?;
-        writers[pid].write_all(b"\n")?;
+            for pid in 0..num_partitions as usize {
+                flush_combiner(&mut writers[pid], format, key_field, value_field, &mut combiners[pid])?;
+            }
+        }
     }
 
     // Flush de todos los writers
@@ -627,32 +1886,78 @@ pub fn shuffle_to_partitions(
     Ok(parts)
 }
 
-/// Lee un archivo de partición (JSONL) y devuelve su contenido como Records.
-pub fn read_partition(path: &str) -> io::Result<Records> {
+/// Lee un archivo de partición y devuelve su contenido como Records, en
+/// el formato con que fue escrita (ver `PartitionFormat`).
+pub fn read_partition(path: &str, format: PartitionFormat) -> io::Result<Records> {
     let file = File::open(path)?;
-    let reader = BufReader::new(file);
+    let mut reader = BufReader::new(file);
 
-    let mut out = Vec::new();
-    for line in reader.lines() {
-        let line = line?;
-        if line.trim().is_empty() {
-            continue;
+    match format {
+        PartitionFormat::Jsonl => {
+            let mut out = Vec::new();
+            for line in reader.lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let rec: Value = serde_json::from_str(&line)?;
+                out.push(rec);
+            }
+            Ok(out)
+        }
+        PartitionFormat::Binary => {
+            let mut out = Vec::new();
+            while let Some(rec) = read_length_prefixed::<Record, _>(&mut reader)? {
+                out.push(rec);
+            }
+            Ok(out)
         }
-        let rec: Value = serde_json::from_str(&line)?;
-        out.push(rec);
     }
-
-    Ok(out)
 }
 
 /// Reduce todas las particiones (que ya tienen {key_field, value_field})
 /// y escribe el resultado en un archivo CSV simple:
 ///   key_field,value_field (sin encabezado)
+///
+/// Atajo de `reduce_partitions_to_file_with` con el aggregator `sum`
+/// (el comportamiento histórico de este operador).
 pub fn reduce_partitions_to_file(
     partitions: &[Partition],
     key_field: &str,
     value_field: &str,
     output_path: &str,
+) -> io::Result<()> {
+    reduce_partitions_to_file_with(partitions, key_field, value_field, output_path, "sum", false)
+}
+
+/// reduce_partitions_to_file genérico: igual, pero con el aggregator
+/// que le pases por nombre (ver `aggregator_for`), con spill a disco
+/// acotado por `max_in_mem_keys()` igual que antes — la diferencia es
+/// que ahora lo que se spillea es el acumulador *parcial* del
+/// aggregator (serializable vía `Acc`), no un número ya sumado.
+///
+/// `combined` indica si las particiones ya pasaron por el combiner de
+/// `shuffle_to_partitions_with` (su `value_field` ya es un acumulador
+/// parcial, no un valor crudo): en ese caso se mergea con `add_acc` en
+/// vez de `add`, para no re-inicializar ni tratar el parcial como si
+/// fuera un único valor nuevo.
+///
+/// `key_field`/`value_field` aceptan nombre plano o JSONPath (ver
+/// `jsonpath::get_selector`).
+///
+/// Las particiones se reparten en grupos contiguos entre un pool
+/// acotado de workers (ver `max_concurrency`/`MINISPARK_SEQUENTIAL`):
+/// como ya vienen hash-particionadas por la misma clave, ninguna clave
+/// puede caer en dos grupos, así que cada grupo se reduce en su propio
+/// hilo de forma completamente independiente y el resultado final sólo
+/// concatena los CSV de cada grupo en orden (ver `concat_files_in_order`).
+pub fn reduce_partitions_to_file_with(
+    partitions: &[Partition],
+    key_field: &str,
+    value_field: &str,
+    output_path: &str,
+    agg_name: &str,
+    combined: bool,
 ) -> io::Result<()> {
     // Si no hay particiones, generamos un archivo vacío y salimos.
     if partitions.is_empty() {
@@ -669,27 +1974,89 @@ pub fn reduce_partitions_to_file(
     let first_part_dir = Path::new(&partitions[0].path)
         .parent()
         .unwrap_or_else(|| Path::new("/data/tmp"));
+    let spill_root = first_part_dir.join("spill_reduce");
+    fs::create_dir_all(&spill_root)?;
+
+    let workers = if sequential_mode() { 1 } else { max_concurrency() };
+    let chunk_size = chunk_size_for(partitions.len(), workers);
+    let groups: Vec<&[Partition]> = partitions.chunks(chunk_size).collect();
+
+    let group_outputs: Vec<PathBuf> = (0..groups.len())
+        .map(|idx| spill_root.join(format!("group-{idx}.csv")))
+        .collect();
+
+    let mut first_err: Option<io::Error> = None;
+    thread::scope(|scope| {
+        let handles: Vec<_> = groups
+            .iter()
+            .copied()
+            .zip(group_outputs.iter())
+            .enumerate()
+            .map(|(idx, (group, tmp_output))| {
+                let group_spill_dir = spill_root.join(format!("group-{idx}"));
+                scope.spawn(move || -> io::Result<()> {
+                    reduce_partition_group_to_csv(
+                        group,
+                        key_field,
+                        value_field,
+                        agg_name,
+                        combined,
+                        &group_spill_dir,
+                        tmp_output,
+                    )
+                })
+            })
+            .collect();
+
+        for h in handles {
+            match h.join().expect("hilo de reduce paniqueó") {
+                Ok(()) => {}
+                Err(e) if first_err.is_none() => first_err = Some(e),
+                Err(_) => {}
+            }
+        }
+    });
+
+    if let Some(e) = first_err {
+        return Err(e);
+    }
 
-    let spill_dir_path = first_part_dir.join("spill_reduce");
-    let spill_dir_str = spill_dir_path.to_string_lossy().to_string();
+    concat_files_in_order(&group_outputs, output_path)
+}
 
-    let mut agg = SpillingAggregator::new(&spill_dir_str, max_in_mem_keys())?;
+/// Reduce un grupo contiguo de particiones a un CSV propio (un worker
+/// del pool de `reduce_partitions_to_file_with`): es la misma lógica
+/// secuencial de siempre (un `SpillingAggregator` con spill a
+/// `spill_dir`), sólo que acotada a `group` en vez de todas las
+/// particiones.
+fn reduce_partition_group_to_csv(
+    group: &[Partition],
+    key_field: &str,
+    value_field: &str,
+    agg_name: &str,
+    combined: bool,
+    spill_dir: &Path,
+    tmp_output: &Path,
+) -> io::Result<()> {
+    let spill_dir_str = spill_dir.to_string_lossy().to_string();
+    let mut agg = SpillingAggregator::new(&spill_dir_str, max_in_mem_keys(), aggregator_for(agg_name))?;
 
-    for part in partitions {
-        let recs = read_partition(&part.path)?;
+    for part in group {
+        let recs = read_partition(&part.path, part.format)?;
         for rec in recs.into_iter() {
-            if let Some(obj) = rec.as_object() {
-                let key_opt = obj.get(key_field).and_then(|v| v.as_str());
-                let val_opt = obj.get(value_field).and_then(|v| v.as_u64());
-                if let (Some(k), Some(v)) = (key_opt, val_opt) {
+            let key_opt = get_selector(&rec, key_field).and_then(|v| v.as_str());
+            let val_opt = get_selector(&rec, value_field);
+            if let (Some(k), Some(v)) = (key_opt, val_opt) {
+                if combined {
+                    agg.add_acc(k, v.clone())?;
+                } else {
                     agg.add(k, v)?;
                 }
             }
         }
     }
 
-    // Escribir el resultado final a CSV (token,count, o la pareja que toque).
-    agg.finalize_to_csv(output_path)
+    agg.finalize_to_csv(&tmp_output.to_string_lossy())
 }
 
 /* =========================
@@ -702,11 +2069,30 @@ pub fn reduce_partitions_to_file(
 /// 2. Genera registros { "token": <palabra>, "count": 1 }.
 /// 3. Hace shuffle a N particiones en `tmp_dir/wc_stage1_<archivo>/`.
 /// 4. Reduce todas las particiones y escribe CSV en `output_path`.
+///
+/// Atajo de `wordcount_file_shuffled_local_with_tokenizer` con `DefaultTokenizer`.
 pub fn wordcount_file_shuffled_local(
     input_path: &str,
     tmp_dir: &str,
     num_partitions: u32,
     output_path: &str,
+) -> io::Result<()> {
+    wordcount_file_shuffled_local_with_tokenizer(
+        input_path,
+        tmp_dir,
+        num_partitions,
+        output_path,
+        &DefaultTokenizer::default(),
+    )
+}
+
+/// Igual que `wordcount_file_shuffled_local`, pero con el `Tokenizer` que le pases.
+pub fn wordcount_file_shuffled_local_with_tokenizer(
+    input_path: &str,
+    tmp_dir: &str,
+    num_partitions: u32,
+    output_path: &str,
+    tokenizer: &dyn Tokenizer,
 ) -> io::Result<()> {
     // 1) Leer líneas
     let file = File::open(input_path)?;
@@ -714,7 +2100,7 @@ pub fn wordcount_file_shuffled_local(
     let lines = reader.lines().map(|l| l.unwrap_or_default());
 
     // 2) Stage1: líneas -> tokens {token,count=1}
-    let token_records = wc_stage1_make_token_records(lines);
+    let token_records = wc_stage1_make_token_records_with(lines, tokenizer);
 
     // 3) Shuffle: token -> particiones por hash(token)
     //    Stage_id único por archivo
@@ -724,33 +2110,220 @@ pub fn wordcount_file_shuffled_local(
         .unwrap_or("nofile");
     let stage_id = format!("wc_stage1_{}", file_key);
 
-    let partitions =
-        shuffle_to_partitions(token_records, "token", num_partitions, tmp_dir, &stage_id)?;
+    // Combine del lado del map: ver wordcount_csv_file_shuffled_local.
+    let partitions = shuffle_to_partitions_with(
+        token_records,
+        "token",
+        num_partitions,
+        tmp_dir,
+        &stage_id,
+        Some(("count", "sum")),
+    )?;
 
-    // 4) Reduce: sum(count) por token en todas las particiones
-    reduce_partitions_to_file(&partitions, "token", "count", output_path)
+    // 4) Reduce: sum(count) por token en todas las particiones (ya combinadas)
+    reduce_partitions_to_file_with(&partitions, "token", "count", output_path, "sum", true)
 }
 
 /* =========================
    Ejecutar DAG de WordCount para un archivo
    ========================= */
 
-/// Ejecuta un DAG de WordCount para **un solo archivo de entrada**.
-///
-/// Usa sólo el nodo de lectura:
-/// - busca un nodo `read_*`
-/// - interpreta `op` ("read_csv", "read_jsonl", "read_text", etc.)
-/// - usa `partitions` del nodo si viene; si no, usa `num_partitions` por defecto.
-///
-/// Por ahora asumimos:
+/// Cuántos hilos como máximo lista `list_files_bounded` en simultáneo.
+/// Evita que un árbol de directorios enorme dispare un hilo por
+/// subdirectorio (fan-out sin límite); en cambio, cada nivel del árbol
+/// se procesa en tandas de a lo sumo este tamaño.
+const DEFAULT_LISTING_WORKERS: usize = 8;
+
+fn is_glob_pattern(s: &str) -> bool {
+    s.contains('*') || s.contains('?') || s.contains('[')
+}
+
+/// Columnas hive-style extraídas del path de un archivo: segmentos tipo
+/// `col=value` (p.ej. `lang=es/day=2024-01-01/part.jsonl` da
+/// `[("lang","es"), ("day","2024-01-01")]`), en el orden en que aparecen.
+/// No se mira el nombre de archivo final, sólo directorios intermedios.
+fn extract_hive_partitions(path: &Path) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut components: Vec<_> = path.components().collect();
+    components.pop(); // el nombre de archivo no es una columna de partición
+
+    for comp in components {
+        if let std::path::Component::Normal(seg) = comp {
+            if let Some((k, v)) = seg.to_str().and_then(|s| s.split_once('=')) {
+                if !k.is_empty() && !v.is_empty() {
+                    out.push((k.to_string(), v.to_string()));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+/// Para un patrón glob, el directorio raíz desde el que arrancar el
+/// listado: el prefijo de componentes que no tienen metacaracteres.
+fn glob_root(path: &Path) -> PathBuf {
+    let mut root = PathBuf::new();
+    for comp in path.components() {
+        if let std::path::Component::Normal(seg) = comp {
+            if is_glob_pattern(seg.to_str().unwrap_or("")) {
+                break;
+            }
+        }
+        root.push(comp.as_os_str());
+    }
+    if root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        root
+    }
+}
+
+/// Enumera, con un pool acotado de hilos, todos los archivos bajo `root`
+/// (recursivamente) que matchean `pattern` (si no hay patrón, matchean
+/// todos). Procesa el árbol nivel por nivel: en cada nivel, lista a lo
+/// sumo `workers` directorios en paralelo, como un listado de
+/// object-store con prefijo+delimitador pero con concurrencia acotada en
+/// vez de recursar sin límite.
+fn list_files_bounded(
+    root: &Path,
+    pattern: Option<&Pattern>,
+    workers: usize,
+) -> io::Result<Vec<PathBuf>> {
+    let workers = workers.max(1);
+    let mut frontier: Vec<PathBuf> = vec![root.to_path_buf()];
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    while !frontier.is_empty() {
+        let mut next_frontier: Vec<PathBuf> = Vec::new();
+
+        for batch in frontier.chunks(workers) {
+            let mut batch_err: Option<io::Error> = None;
+            thread::scope(|scope| {
+                let handles: Vec<_> = batch
+                    .iter()
+                    .map(|dir| scope.spawn(move || -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+                        let mut sub_dirs = Vec::new();
+                        let mut matched = Vec::new();
+                        for entry in fs::read_dir(dir)?.flatten() {
+                            let path = entry.path();
+                            if path.is_dir() {
+                                sub_dirs.push(path);
+                            } else if pattern.map(|p| p.matches_path(&path)).unwrap_or(true) {
+                                matched.push(path);
+                            }
+                        }
+                        Ok((sub_dirs, matched))
+                    }))
+                    .collect();
+
+                for h in handles {
+                    match h.join().expect("hilo de listado de directorio paniqueó") {
+                        Ok((mut sub_dirs, mut matched)) => {
+                            next_frontier.append(&mut sub_dirs);
+                            files.append(&mut matched);
+                        }
+                        Err(e) if batch_err.is_none() => batch_err = Some(e),
+                        Err(_) => {}
+                    }
+                }
+            });
+
+            if let Some(e) = batch_err {
+                return Err(e);
+            }
+        }
+
+        frontier = next_frontier;
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Resuelve `input_path` a la lista de archivos que efectivamente hay
+/// que leer, cada uno con sus columnas hive-style ya extraídas de su
+/// path (ver `extract_hive_partitions`). Soporta tres formas:
+/// - Un archivo suelto: se devuelve tal cual.
+/// - Un patrón glob/`**`: se resuelve el directorio raíz antes del
+///   primer metacaracter y se lista desde ahí con `list_files_bounded`.
+/// - Un directorio: se listan recursivamente todos sus archivos.
+fn list_input_files(input_path: &str) -> io::Result<Vec<(PathBuf, Vec<(String, String)>)>> {
+    let path = Path::new(input_path);
+
+    let files = if path.is_file() {
+        vec![path.to_path_buf()]
+    } else if is_glob_pattern(input_path) {
+        let root = glob_root(path);
+        let pattern = Pattern::new(input_path).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("patrón glob inválido '{input_path}': {e}"),
+            )
+        })?;
+        list_files_bounded(&root, Some(&pattern), DEFAULT_LISTING_WORKERS)?
+    } else {
+        list_files_bounded(path, None, DEFAULT_LISTING_WORKERS)?
+    };
+
+    Ok(files
+        .into_iter()
+        .map(|f| {
+            let hive_cols = extract_hive_partitions(&f);
+            (f, hive_cols)
+        })
+        .collect())
+}
+
+/// Ejecuta un DAG de WordCount para una entrada que puede ser **un solo
+/// archivo, un directorio o un patrón glob/`**`** (ver `list_input_files`):
+/// todos los archivos que matcheen se tratan como una sola entrada
+/// lógica, con un único shuffle/reduce en común.
+///
+/// Usa sólo el nodo de lectura:
+/// - busca un nodo `read_*`
+/// - interpreta `op` ("read_csv", "read_jsonl", "read_text", etc.)
+/// - usa `partitions` del nodo si viene; si no, usa `num_partitions` por defecto.
+///
+/// Por ahora asumimos:
 ///   - CSV/JSONL tienen un campo `"text"` con el contenido.
 ///   - El resto del pipeline (flat_map/map/reduce_by_key) está fijo para WordCount.
+///
+/// Si el path de un archivo trae segmentos hive-style (`col=valor`, p.ej.
+/// `lang=es/day=2024-01-01/part.jsonl`), esas columnas se agregan a cada
+/// registro tokenizado que salga de ese archivo. El combine del lado del
+/// map y el reduce fijo de WordCount sólo conservan `token` y `count`, así
+/// que esas columnas no llegan al archivo de salida ni se agrupa por ellas.
+/// Atajo de `execute_wordcount_dag_for_file_with` con el combine del lado
+/// del map activado (el comportamiento histórico de este operador).
 pub fn execute_wordcount_dag_for_file(
     dag: &Dag,
     input_path: &str,
     tmp_dir: &str,
     default_num_partitions: u32,
     output_path: &str,
+) -> io::Result<()> {
+    execute_wordcount_dag_for_file_with(
+        dag,
+        input_path,
+        tmp_dir,
+        default_num_partitions,
+        output_path,
+        true,
+    )
+}
+
+/// Igual que `execute_wordcount_dag_for_file`, pero con `combine` para
+/// desactivar el combine del lado del map (ver `shuffle_to_partitions_with`):
+/// sirve para pipelines no sumables, donde mezclar valores parciales antes
+/// del reduce cambiaría el resultado final.
+pub fn execute_wordcount_dag_for_file_with(
+    dag: &Dag,
+    input_path: &str,
+    tmp_dir: &str,
+    default_num_partitions: u32,
+    output_path: &str,
+    combine: bool,
 ) -> io::Result<()> {
     // 1) Buscar un nodo de lectura: op que empiece con "read_"
     let read_node = dag
@@ -768,48 +2341,81 @@ pub fn execute_wordcount_dag_for_file(
     // 3) Campo de texto para CSV/JSONL (por ahora fijo)
     let text_field = "text";
 
-    // 4) Extensión del archivo (por si necesitamos inferir)
-    let ext = Path::new(input_path)
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_ascii_lowercase();
+    // 4) Listamos todos los archivos que matchean `input_path`
+    let files = list_input_files(input_path)?;
 
-    // 5) Determinar formato a partir de op / extensión
-    let effective_format = match read_node.op.as_str() {
-        "read_csv" => "csv",
-        "read_jsonl" => "jsonl",
-        "read_json" => "json",
-        "read_text" | "read_text_glob" => "text",
-        _ => {
-            // fallback: inferimos por extensión
-            if ext == "csv" {
-                "csv"
-            } else if ext == "json" || ext == "jsonl" {
-                "jsonl"
-            } else {
-                "text"
+    if files.is_empty() {
+        // Igual que el resto del motor: entrada vacía => archivo de salida vacío
+        if let Some(parent) = Path::new(output_path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
             }
         }
-    };
+        let _ = File::create(output_path)?;
+        return Ok(());
+    }
+
+    // 5) Leemos cada archivo, tokenizamos, e inyectamos sus columnas hive
+    let mut token_records: Records = Vec::new();
+    for (file_path, hive_cols) in &files {
+        let file_str = file_path.to_string_lossy().to_string();
+
+        let ext = file_path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        let effective_format = match read_node.op.as_str() {
+            "read_csv" => "csv",
+            "read_jsonl" => "jsonl",
+            "read_json" => "json",
+            "read_text" | "read_text_glob" => "text",
+            _ => {
+                if ext == "csv" {
+                    "csv"
+                } else if ext == "json" || ext == "jsonl" {
+                    "jsonl"
+                } else {
+                    "text"
+                }
+            }
+        };
+
+        let recs = match effective_format {
+            "csv" => read_csv_to_records(&file_str)?,
+            "jsonl" | "json" => read_jsonl_to_records(&file_str)?,
+            _ => read_text_to_records(&file_str)?,
+        };
+
+        let mut recs = wc_stage1_from_records(recs, text_field);
 
-    match effective_format {
-        "csv" => wordcount_csv_file_shuffled_local(
-            input_path,
-            text_field,
-            tmp_dir,
-            num_partitions,
-            output_path,
-        ),
-        "jsonl" | "json" => wordcount_jsonl_file_shuffled_local(
-            input_path,
-            text_field,
-            tmp_dir,
-            num_partitions,
-            output_path,
-        ),
-        _ => wordcount_file_shuffled_local(input_path, tmp_dir, num_partitions, output_path),
+        if !hive_cols.is_empty() {
+            for rec in &mut recs {
+                if let Some(obj) = rec.as_object_mut() {
+                    for (k, v) in hive_cols {
+                        obj.insert(k.clone(), json!(v));
+                    }
+                }
+            }
+        }
+
+        token_records.append(&mut recs);
     }
+
+    // 6) Shuffle + reduce de todos los tokens juntos, como una sola entrada lógica
+    // (con combine del lado del map, ver wordcount_csv_file_shuffled_local)
+    let stage_id = "wc_stage1_listing";
+    let combine_spec = combine.then_some(("count", "sum"));
+    let partitions = shuffle_to_partitions_with(
+        token_records,
+        "token",
+        num_partitions,
+        tmp_dir,
+        stage_id,
+        combine_spec,
+    )?;
+    reduce_partitions_to_file_with(&partitions, "token", "count", output_path, "sum", combine)
 }
 
 /* =========================
@@ -820,6 +2426,13 @@ pub fn execute_wordcount_dag_for_file(
 /// - `left_parts` y `right_parts` deben venir de `shuffle_to_partitions`
 ///   usando el mismo `key_field` y el mismo `num_partitions`.
 /// - Escribe el resultado en un archivo JSONL en `output_path`.
+///
+/// Las particiones izquierdas se reparten en grupos contiguos entre un
+/// pool acotado de workers (ver `max_concurrency`/`MINISPARK_SEQUENTIAL`):
+/// cada partición izquierda sólo puede matchear con la derecha que
+/// comparte su mismo id, así que cada grupo se joinea en su propio hilo
+/// de forma independiente y el resultado final sólo concatena el JSONL
+/// de cada grupo en orden (ver `concat_files_in_order`).
 pub fn join_partitions_to_jsonl(
     left_parts: &[Partition],
     right_parts: &[Partition],
@@ -832,26 +2445,80 @@ pub fn join_partitions_to_jsonl(
         right_by_id.insert(p.id, p);
     }
 
-    // Crear carpeta de salida si hace falta
     if let Some(parent) = Path::new(output_path).parent() {
         if !parent.as_os_str().is_empty() {
             fs::create_dir_all(parent)?;
         }
     }
 
-    let out = File::create(output_path)?;
-    let mut writer = BufWriter::new(out);
+    if left_parts.is_empty() {
+        let _ = File::create(output_path)?;
+        return Ok(());
+    }
+
+    let spill_root = Path::new(&left_parts[0].path)
+        .parent()
+        .unwrap_or_else(|| Path::new("/data/tmp"))
+        .join("join_groups");
+    fs::create_dir_all(&spill_root)?;
+
+    let workers = if sequential_mode() { 1 } else { max_concurrency() };
+    let chunk_size = chunk_size_for(left_parts.len(), workers);
+    let groups: Vec<&[Partition]> = left_parts.chunks(chunk_size).collect();
+
+    let group_outputs: Vec<PathBuf> = (0..groups.len())
+        .map(|idx| spill_root.join(format!("group-{idx}.jsonl")))
+        .collect();
+
+    let right_by_id_ref = &right_by_id;
+    let mut first_err: Option<io::Error> = None;
+    thread::scope(|scope| {
+        let handles: Vec<_> = groups
+            .iter()
+            .copied()
+            .zip(group_outputs.iter())
+            .map(|(group, tmp_output)| {
+                scope.spawn(move || -> io::Result<()> {
+                    join_partition_group_to_jsonl(group, right_by_id_ref, key_field, tmp_output)
+                })
+            })
+            .collect();
 
-    // Para cada partición izquierda, buscamos la correspondiente derecha
-    for lpart in left_parts {
+        for h in handles {
+            match h.join().expect("hilo de join paniqueó") {
+                Ok(()) => {}
+                Err(e) if first_err.is_none() => first_err = Some(e),
+                Err(_) => {}
+            }
+        }
+    });
+
+    if let Some(e) = first_err {
+        return Err(e);
+    }
+
+    concat_files_in_order(&group_outputs, output_path)
+}
+
+/// Joinea un grupo contiguo de particiones izquierdas contra sus
+/// correspondientes particiones derechas (por id) y escribe el
+/// resultado como JSONL propio: un worker del pool de
+/// `join_partitions_to_jsonl`.
+fn join_partition_group_to_jsonl(
+    group: &[Partition],
+    right_by_id: &HashMap<u32, &Partition>,
+    key_field: &str,
+    tmp_output: &Path,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(tmp_output)?);
+
+    for lpart in group {
         if let Some(rpart) = right_by_id.get(&lpart.id) {
-            let lrecs = read_partition(&lpart.path)?;
-            let rrecs = read_partition(&rpart.path)?;
+            let lrecs = read_partition(&lpart.path, lpart.format)?;
+            let rrecs = read_partition(&rpart.path, rpart.format)?;
 
-            // join en memoria para esta partición
             let joined = op_join_by_key(lrecs, rrecs, key_field);
 
-            // escribimos los registros como JSONL
             for rec in joined {
                 serde_json::to_writer(&mut writer, &rec)?;
                 writer.write_all(b"\n")?;
@@ -859,6 +2526,366 @@ pub fn join_partitions_to_jsonl(
         }
     }
 
+    writer.flush()
+}
+
+/// Un run ordenado por clave de un lado de `join_partitions_to_jsonl_spilling`:
+/// una lista `(clave, registro)` ya ordenada por clave, en memoria o en
+/// un spill en disco (mismo formato binario length-prefixed que usan los
+/// spills de `SpillingAggregator`). A diferencia de `Run`, acá no se
+/// mergean valores con la misma clave -- el join necesita ver cada
+/// registro, no un acumulado.
+enum JoinRun {
+    Memory {
+        entries: Vec<(String, Record)>,
+        pos: usize,
+    },
+    File {
+        reader: BufReader<File>,
+        path: String,
+    },
+}
+
+impl JoinRun {
+    fn next_entry(&mut self) -> io::Result<Option<(String, Record)>> {
+        match self {
+            JoinRun::Memory { entries, pos } => {
+                if *pos >= entries.len() {
+                    return Ok(None);
+                }
+                let entry = entries[*pos].clone();
+                *pos += 1;
+                Ok(Some(entry))
+            }
+            JoinRun::File { reader, path } => {
+                read_length_prefixed::<(String, Record), _>(reader).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("error al parsear run de join {}: {e}", path),
+                    )
+                })
+            }
+        }
+    }
+}
+
+/// Lee los Records de `parts` en bloques acotados por `max_in_mem_keys()`,
+/// ordena cada bloque por `key_field` y lo vuelca como un run en
+/// `spill_dir` cuando el bloque llega al umbral; lo que sobra al final
+/// queda como un run en memoria. De paso junta los nombres de campo de
+/// todos los registros (salvo `key_field`), para poder fabricar más
+/// tarde un lado "nulo" con las mismas columnas en los outer join.
+fn build_sorted_runs(
+    parts: &[Partition],
+    key_field: &str,
+    spill_dir: &Path,
+) -> io::Result<(Vec<JoinRun>, HashSet<String>)> {
+    fs::create_dir_all(spill_dir)?;
+
+    let key_name = jsonpath::leaf_name(key_field);
+    let threshold = max_in_mem_keys();
+    let mut runs: Vec<JoinRun> = Vec::new();
+    let mut chunk: Vec<(String, Record)> = Vec::new();
+    let mut spill_counter = 0usize;
+    let mut fields: HashSet<String> = HashSet::new();
+
+    for part in parts {
+        let recs = read_partition(&part.path, part.format)?;
+        for rec in recs.into_iter() {
+            if let Some(obj) = rec.as_object() {
+                for k in obj.keys() {
+                    if *k != key_name {
+                        fields.insert(k.clone());
+                    }
+                }
+            }
+
+            let key = get_selector(&rec, key_field)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+            chunk.push((key, rec));
+
+            if chunk.len() >= threshold {
+                spill_chunk_as_run(&mut chunk, spill_dir, &mut spill_counter, &mut runs)?;
+            }
+        }
+    }
+
+    if !chunk.is_empty() {
+        chunk.sort_by(|a, b| a.0.cmp(&b.0));
+        runs.push(JoinRun::Memory {
+            entries: chunk,
+            pos: 0,
+        });
+    }
+
+    Ok((runs, fields))
+}
+
+/// Ordena `chunk` por clave y lo escribe como un nuevo run en disco,
+/// dejando `chunk` vacío para que quien llama lo siga llenando.
+fn spill_chunk_as_run(
+    chunk: &mut Vec<(String, Record)>,
+    spill_dir: &Path,
+    spill_counter: &mut usize,
+    runs: &mut Vec<JoinRun>,
+) -> io::Result<()> {
+    if chunk.is_empty() {
+        return Ok(());
+    }
+
+    *spill_counter += 1;
+    let pid = std::process::id();
+    let filename = format!("join-run-{}-{}.bin", pid, spill_counter);
+    let path = spill_dir.join(filename);
+
+    chunk.sort_by(|a, b| a.0.cmp(&b.0));
+    {
+        let mut writer = BufWriter::new(File::create(&path)?);
+        for entry in chunk.iter() {
+            write_length_prefixed(&mut writer, entry)?;
+        }
+        writer.flush()?;
+    }
+    chunk.clear();
+
+    let file = File::open(&path)?;
+    runs.push(JoinRun::File {
+        reader: BufReader::new(file),
+        path: path.to_string_lossy().to_string(),
+    });
+    Ok(())
+}
+
+/// K-way merge en streaming sobre varios `JoinRun` ya ordenados por
+/// clave: `next_group` devuelve todos los registros que comparten la
+/// clave mínima entre todos los runs, de a un grupo por vez, así el join
+/// puede avanzar por claves sin tener el lado entero en memoria.
+struct SortedKeyGroups {
+    runs: Vec<JoinRun>,
+    heap: BinaryHeap<Reverse<(String, usize)>>,
+    pending: Vec<Option<(String, Record)>>,
+}
+
+impl SortedKeyGroups {
+    fn new(mut runs: Vec<JoinRun>) -> io::Result<Self> {
+        let mut heap = BinaryHeap::new();
+        let mut pending = Vec::with_capacity(runs.len());
+
+        for (idx, run) in runs.iter_mut().enumerate() {
+            let next = run.next_entry()?;
+            if let Some((k, _)) = &next {
+                heap.push(Reverse((k.clone(), idx)));
+            }
+            pending.push(next);
+        }
+
+        Ok(Self { runs, heap, pending })
+    }
+
+    fn next_group(&mut self) -> io::Result<Option<(String, Vec<Record>)>> {
+        let Some(Reverse((first_key, first_idx))) = self.heap.pop() else {
+            return Ok(None);
+        };
+
+        let mut group = Vec::new();
+        group.push(self.take_pending_and_advance(first_idx)?);
+
+        loop {
+            let same_key = matches!(self.heap.peek(), Some(Reverse((k, _))) if *k == first_key);
+            if !same_key {
+                break;
+            }
+            let Reverse((_, idx)) = self.heap.pop().unwrap();
+            group.push(self.take_pending_and_advance(idx)?);
+        }
+
+        Ok(Some((first_key, group)))
+    }
+
+    /// Saca el registro pendiente del run `idx`, lo repone leyendo la
+    /// siguiente entrada de ese mismo run y la vuelve a meter al heap si
+    /// corresponde.
+    fn take_pending_and_advance(&mut self, idx: usize) -> io::Result<Record> {
+        let (_, rec) = self.pending[idx]
+            .take()
+            .expect("heap sólo referencia runs con entrada pendiente");
+
+        let next = self.runs[idx].next_entry()?;
+        if let Some((k, _)) = &next {
+            self.heap.push(Reverse((k.clone(), idx)));
+        }
+        self.pending[idx] = next;
+
+        Ok(rec)
+    }
+}
+
+fn write_jsonl_record(writer: &mut BufWriter<File>, rec: &Record) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, rec)?;
+    writer.write_all(b"\n")
+}
+
+/// Emite las filas de un grupo de la izquierda que no tuvo match a la
+/// derecha, según `join_type` (sólo hace algo para `LeftOuter`/`FullOuter`/`LeftAnti`).
+fn emit_left_unmatched(
+    writer: &mut BufWriter<File>,
+    group: &[Record],
+    join_type: JoinType,
+    right_fields: &HashSet<String>,
+    key_field: &str,
+) -> io::Result<()> {
+    match join_type {
+        JoinType::LeftOuter | JoinType::FullOuter => {
+            let right_null = null_side_record(right_fields, key_field, None);
+            for lrec in group {
+                write_jsonl_record(writer, &merge_records(lrec, &right_null, key_field))?;
+            }
+        }
+        JoinType::LeftAnti => {
+            for lrec in group {
+                write_jsonl_record(writer, lrec)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Emite las filas de un grupo de la derecha que no tuvo match a la
+/// izquierda, según `join_type` (sólo hace algo para `RightOuter`/`FullOuter`).
+fn emit_right_unmatched(
+    writer: &mut BufWriter<File>,
+    key: &str,
+    group: &[Record],
+    join_type: JoinType,
+    left_fields: &HashSet<String>,
+    key_field: &str,
+) -> io::Result<()> {
+    if matches!(join_type, JoinType::RightOuter | JoinType::FullOuter) {
+        let left_null = null_side_record(left_fields, key_field, Some(key));
+        for rrec in group {
+            write_jsonl_record(writer, &merge_records(&left_null, rrec, key_field))?;
+        }
+    }
+    Ok(())
+}
+
+/// Emite el cross-product de dos grupos con la misma clave, según
+/// `join_type` (para `LeftSemi`/`LeftAnti` no fusiona, sólo decide si la
+/// fila de la izquierda sale o no).
+fn emit_matched(
+    writer: &mut BufWriter<File>,
+    left_group: &[Record],
+    right_group: &[Record],
+    join_type: JoinType,
+    key_field: &str,
+) -> io::Result<()> {
+    match join_type {
+        JoinType::LeftSemi => {
+            for lrec in left_group {
+                write_jsonl_record(writer, lrec)?;
+            }
+        }
+        JoinType::LeftAnti => {
+            // hay match, así que un anti join no emite nada para esta clave.
+        }
+        _ => {
+            for lrec in left_group {
+                for rrec in right_group {
+                    write_jsonl_record(writer, &merge_records(lrec, rrec, key_field))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Directorio de spill para el sort-merge de
+/// `join_partitions_to_jsonl_spilling`: al lado de la primera partición
+/// que haya (izquierda o derecha), igual que hace
+/// `reduce_partitions_to_file_with` con su propio spill.
+fn join_spill_root(left_parts: &[Partition], right_parts: &[Partition]) -> PathBuf {
+    left_parts
+        .first()
+        .or_else(|| right_parts.first())
+        .and_then(|p| Path::new(&p.path).parent())
+        .map(|p| p.join("spill_join"))
+        .unwrap_or_else(|| Path::new("/data/tmp/spill_join").to_path_buf())
+}
+
+/// Join entre dos conjuntos de particiones con sort-merge externo:
+/// ordena cada lado en runs acotados por `max_in_mem_keys()` (como
+/// `SpillingAggregator`), los mergea en streaming por clave
+/// (`SortedKeyGroups`) y avanza los dos cursores ordenados en lockstep
+/// para emitir el join -- a diferencia de `join_partitions_to_jsonl`
+/// (que indexa cada partición derecha entera en un `HashMap`), ninguna
+/// partición necesita entrar completa en memoria. `join_type` define qué
+/// hacer con las claves que sólo aparecen de un lado (ver `JoinType`).
+pub fn join_partitions_to_jsonl_spilling(
+    left_parts: &[Partition],
+    right_parts: &[Partition],
+    key_field: &str,
+    output_path: &str,
+    join_type: JoinType,
+) -> io::Result<()> {
+    if let Some(parent) = Path::new(output_path).parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    let spill_root = join_spill_root(left_parts, right_parts);
+    let (left_runs, left_fields) =
+        build_sorted_runs(left_parts, key_field, &spill_root.join("left"))?;
+    let (right_runs, right_fields) =
+        build_sorted_runs(right_parts, key_field, &spill_root.join("right"))?;
+
+    let mut left_groups = SortedKeyGroups::new(left_runs)?;
+    let mut right_groups = SortedKeyGroups::new(right_runs)?;
+
+    let out = File::create(output_path)?;
+    let mut writer = BufWriter::new(out);
+
+    let mut left_next = left_groups.next_group()?;
+    let mut right_next = right_groups.next_group()?;
+
+    loop {
+        match (&left_next, &right_next) {
+            (None, None) => break,
+            (Some(_), None) => {
+                let (_, lgroup) = left_next.take().unwrap();
+                emit_left_unmatched(&mut writer, &lgroup, join_type, &right_fields, key_field)?;
+                left_next = left_groups.next_group()?;
+            }
+            (None, Some(_)) => {
+                let (rk, rgroup) = right_next.take().unwrap();
+                emit_right_unmatched(&mut writer, &rk, &rgroup, join_type, &left_fields, key_field)?;
+                right_next = right_groups.next_group()?;
+            }
+            (Some((lk, _)), Some((rk, _))) => match lk.cmp(rk) {
+                Ordering::Less => {
+                    let (_, lgroup) = left_next.take().unwrap();
+                    emit_left_unmatched(&mut writer, &lgroup, join_type, &right_fields, key_field)?;
+                    left_next = left_groups.next_group()?;
+                }
+                Ordering::Greater => {
+                    let (rk, rgroup) = right_next.take().unwrap();
+                    emit_right_unmatched(&mut writer, &rk, &rgroup, join_type, &left_fields, key_field)?;
+                    right_next = right_groups.next_group()?;
+                }
+                Ordering::Equal => {
+                    let (_, lgroup) = left_next.take().unwrap();
+                    let (_, rgroup) = right_next.take().unwrap();
+                    emit_matched(&mut writer, &lgroup, &rgroup, join_type, key_field)?;
+                    left_next = left_groups.next_group()?;
+                    right_next = right_groups.next_group()?;
+                }
+            },
+        }
+    }
+
     writer.flush()?;
     Ok(())
 }
@@ -900,6 +2927,681 @@ pub fn join_csv_in_memory(
     Ok(())
 }
 
+/// Igual que `join_csv_in_memory`, pero ninguno de los dos lados necesita
+/// entrar completo en memoria: hace shuffle de cada CSV a `num_partitions`
+/// particiones en disco (ver `shuffle_to_partitions`) y después el
+/// sort-merge externo de `join_partitions_to_jsonl_spilling`, en vez de
+/// indexar el lado derecho entero en un `HashMap` como `op_join_by_key`.
+/// Útil cuando uno de los dos CSV es demasiado grande para `join_csv_in_memory`.
+pub fn join_csv_files_shuffled_local(
+    left_path: &str,
+    right_path: &str,
+    key_field: &str,
+    tmp_dir: &str,
+    num_partitions: u32,
+    output_path: &str,
+    join_type: JoinType,
+) -> io::Result<()> {
+    let left = read_csv_to_records(left_path)?;
+    let right = read_csv_to_records(right_path)?;
+
+    let left_parts = shuffle_to_partitions(left, key_field, num_partitions, tmp_dir, "join_left")?;
+    let right_parts =
+        shuffle_to_partitions(right, key_field, num_partitions, tmp_dir, "join_right")?;
+
+    join_partitions_to_jsonl_spilling(&left_parts, &right_parts, key_field, output_path, join_type)
+}
+
+/* =========================
+   Intérprete genérico de DAGs (pipelines arbitrarios)
+   ========================= */
+
+/// Cuántos registros procesa como máximo cada llamada a `PipelineState::step`
+/// en un operador "angosto" (map/filter/flat_map). Los operadores "anchos"
+/// (reduce_by_key/join) y la lectura de la entrada siguen siendo atómicos,
+/// igual que el resto de los helpers de este archivo.
+const RECORDS_PER_QUANTUM: usize = 2_000;
+
+fn infer_format_from_ext(path: &str) -> &'static str {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    if ext == "csv" {
+        "csv"
+    } else if ext == "json" || ext == "jsonl" {
+        "jsonl"
+    } else {
+        "text"
+    }
+}
+
+fn read_text_to_records(path: &str) -> io::Result<Records> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let mut out = Records::new();
+    for line in reader.lines() {
+        out.push(wc_map_line_to_record(&line?));
+    }
+    Ok(out)
+}
+
+fn read_side_input(path: &str) -> io::Result<Records> {
+    match infer_format_from_ext(path) {
+        "csv" => read_csv_to_records(path),
+        "jsonl" => read_jsonl_to_records(path),
+        _ => read_text_to_records(path),
+    }
+}
+
+/// Lee la entrada de un nodo `read_*`. `input_path` puede traer varias
+/// rutas separadas por coma (así llegan las tareas de etapas >= 1, con los
+/// archivos que le tocaron tras el shuffle).
+fn read_source_records(op: &str, input_path: &str) -> io::Result<Records> {
+    let mut out = Records::new();
+
+    for path in input_path.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let format = match op {
+            "read_csv" => "csv",
+            "read_jsonl" | "read_json" => "jsonl",
+            "read_text" | "read_text_glob" => "text",
+            _ => infer_format_from_ext(path),
+        };
+
+        let mut recs = match format {
+            "csv" => read_csv_to_records(path)?,
+            "jsonl" => read_jsonl_to_records(path)?,
+            _ => read_text_to_records(path)?,
+        };
+        out.append(&mut recs);
+    }
+
+    Ok(out)
+}
+
+/// La entrada de una tarea de etapa >= 1 ya es un shuffle de salidas
+/// JSONL producidas por la etapa anterior (ver `shuffle_next_stage_inputs`
+/// del master), así que no pasa por ningún nodo `read_*` propio.
+fn read_shuffled_records(input_path: &str) -> io::Result<Records> {
+    let mut out = Records::new();
+    for path in input_path.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let mut recs = read_jsonl_to_records(path)?;
+        out.append(&mut recs);
+    }
+    Ok(out)
+}
+
+/// map con nombre: por ahora sólo conocemos "to_lower" (minúsculas a todos
+/// los campos string del registro). Un nombre desconocido se trata como
+/// identidad para no tirar abajo la tarea por una función que no existe.
+fn apply_named_map(name: &str, rec: &Record) -> Record {
+    match name {
+        "to_lower" => {
+            if let Some(obj) = rec.as_object() {
+                let mut out = serde_json::Map::new();
+                for (k, v) in obj {
+                    let nv = match v {
+                        Value::String(s) => json!(s.to_lowercase()),
+                        other => other.clone(),
+                    };
+                    out.insert(k.clone(), nv);
+                }
+                Value::Object(out)
+            } else {
+                rec.clone()
+            }
+        }
+        _ => rec.clone(),
+    }
+}
+
+/// flat_map con nombre: "tokenize" reusa la misma tokenización de WordCount.
+fn apply_named_flat_map(name: &str, rec: &Record) -> Vec<Record> {
+    match name {
+        "tokenize" => wc_flat_map_tokenize(rec),
+        _ => vec![rec.clone()],
+    }
+}
+
+/// filter con nombre: "nonempty_token" reusa el filtro de WordCount. Un
+/// nombre desconocido deja pasar todo.
+fn apply_named_filter(name: &str, rec: &Record) -> bool {
+    match name {
+        "nonempty_token" => wc_filter_nonempty(rec),
+        _ => true,
+    }
+}
+
+/// Convierte un error de `mlua` (compilación o ejecución) en un `io::Error`
+/// para que se propague como falla de la tarea (`ActiveTask::step` -> el
+/// worker reporta `TaskCompleteRequest { success: false }`).
+fn lua_err(msg: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("error en UDF Lua: {msg}"))
+}
+
+/// UDF en Lua asociada a un nodo del DAG (`DagNode.fn_src`). Se compila una
+/// sola vez (ver `PipelineState::udf_for_node`) y se reusa en cada quantum,
+/// en vez de recompilar el chunk por registro.
+///
+/// El estado de Lua se crea con una lista de librerías reducida (sin `os`
+/// ni `io`) para que una UDF no pueda tocar el filesystem ni el entorno del
+/// worker por fuera del registro que se le pasa.
+#[derive(Clone)]
+pub struct LuaUdf {
+    lua: mlua::Lua,
+    func: mlua::Function,
+}
+
+impl LuaUdf {
+    pub fn compile(src: &str) -> Result<Self, String> {
+        let libs = mlua::StdLib::TABLE | mlua::StdLib::STRING | mlua::StdLib::MATH;
+        let lua = mlua::Lua::new_with(libs, mlua::LuaOptions::default())
+            .map_err(|e| format!("no se pudo inicializar Lua: {e}"))?;
+
+        let func: mlua::Function = lua
+            .load(src)
+            .eval()
+            .map_err(|e| format!("error compilando UDF: {e}"))?;
+
+        Ok(Self { lua, func })
+    }
+
+    /// map/filter: el registro cruza a Lua como tabla (vía serde) y el
+    /// resultado se interpreta como el tipo `T` pedido (Record o bool).
+    fn call_one<T>(&self, rec: &Record) -> Result<T, String>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let lv = self.lua.to_value(rec).map_err(|e| e.to_string())?;
+        let out: mlua::Value = self.func.call(lv).map_err(|e| e.to_string())?;
+        self.lua.from_value(out).map_err(|e| e.to_string())
+    }
+
+    pub fn call_map(&self, rec: &Record) -> Result<Record, String> {
+        self.call_one(rec)
+    }
+
+    pub fn call_filter(&self, rec: &Record) -> Result<bool, String> {
+        self.call_one(rec)
+    }
+
+    pub fn call_flat_map(&self, rec: &Record) -> Result<Vec<Record>, String> {
+        self.call_one(rec)
+    }
+
+    /// reduce_by_key: `(acumulador, valor) -> nuevo acumulador`. El valor
+    /// inicial del acumulador es el primer registro de cada clave (no hay
+    /// un valor de init aparte declarado en el DAG).
+    pub fn call_reduce(&self, acc: &Record, val: &Record) -> Result<Record, String> {
+        let lacc = self.lua.to_value(acc).map_err(|e| e.to_string())?;
+        let lval = self.lua.to_value(val).map_err(|e| e.to_string())?;
+        let out: mlua::Value = self
+            .func
+            .call((lacc, lval))
+            .map_err(|e| e.to_string())?;
+        self.lua.from_value(out).map_err(|e| e.to_string())
+    }
+}
+
+/// Estado de ejecución incremental de un pipeline de operadores genéricos.
+///
+/// A diferencia de `execute_wordcount_dag_for_file` (que sólo miraba el
+/// nodo `read_*` y corría un WordCount fijo), `PipelineState` camina el
+/// `Dag` completo: toma la sub-cadena de nodos que le corresponde a una
+/// tarea (`node_id` viene como "id1>id2>...>idN", ver `schedule::plan_stages`
+/// del master), los ordena topológicamente usando `edges` y va aplicando
+/// cada operador sobre el stream de registros que trae el anterior.
+pub struct PipelineState {
+    nodes: Vec<DagNode>,
+    input_path: String,
+    output_path: String,
+    /// Índice del próximo nodo del pipeline a ejecutar.
+    cursor: usize,
+    /// Si ya se cargó el stream inicial (desde `read_*` o desde el shuffle
+    /// de la etapa anterior).
+    seeded: bool,
+    /// Stream de registros que produjo el último nodo completado.
+    current: Records,
+    /// Acumulador parcial del nodo angosto que se está procesando en
+    /// varios `step()` (de a `RECORDS_PER_QUANTUM` registros por llamada).
+    buf: Records,
+    sub_cursor: usize,
+    done: bool,
+    /// UDFs Lua ya compiladas, una por `DagNode.id` que trae `fn_src`, para
+    /// no recompilar el chunk en cada quantum.
+    lua_udfs: HashMap<String, LuaUdf>,
+    /// Tamaño total en bytes de `input_path` (suma de `fs::metadata` de
+    /// cada ruta separada por coma), conocido de antemano y usado por
+    /// `progress()` para reportarle al worker una fracción completada sin
+    /// tener que leer el archivo primero.
+    total_input_bytes: u64,
+}
+
+/// Resuelve el nombre de una función de `aggregate_by_key` (un elemento
+/// de la lista de specs parseada por `parse_agg_specs`) al `AggFn`
+/// correspondiente, igual que `aggregator_for` para `reduce_by_key`.
+/// Nombres no reconocidos caen a `Sum`, el comportamiento histórico de
+/// este tipo de operador en el engine.
+pub fn agg_fn_for(name: &str) -> AggFn {
+    match name {
+        "count" => AggFn::Count,
+        "min" => AggFn::Min,
+        "max" => AggFn::Max,
+        "avg" | "average" => AggFn::Avg,
+        "collect_list" | "list" => AggFn::CollectList,
+        _ => AggFn::Sum,
+    }
+}
+
+/// Parsea la especificación de campos de un nodo `sort_by`, codificada en
+/// `DagNode.fn_name` como una lista separada por comas de "campo" o
+/// "campo:desc" (por default cada campo es ascendente). Ver `op_sort_by`.
+/// Ej: "grupo,n:desc".
+fn parse_sort_spec(spec: &str) -> Vec<(String, SortOrder)> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|part| match part.split_once(':') {
+            Some((field, "desc")) => (field.trim().to_string(), SortOrder::Desc),
+            Some((field, _)) => (field.trim().to_string(), SortOrder::Asc),
+            None => (part.to_string(), SortOrder::Asc),
+        })
+        .collect()
+}
+
+/// Parsea la lista de valores permitidos de un nodo `is_in`, codificada
+/// en `DagNode.fn_name` separada por comas. Ver `op_is_in`.
+/// Ej: "rojo,verde,azul".
+fn parse_value_set(spec: &str) -> HashSet<String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parsea la lista de specs de un nodo `aggregate_by_key`, codificada en
+/// `DagNode.fn_name` como "campo:fn,campo2:fn2" (ver `op_aggregate_by_key`
+/// y `agg_fn_for`). Un elemento sin ":fn" cae a `sum`, igual que
+/// `aggregator_for`.
+fn parse_agg_specs(spec: &str) -> Vec<(String, AggFn)> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|part| match part.split_once(':') {
+            Some((field, kind)) => (field.trim().to_string(), agg_fn_for(kind.trim())),
+            None => (part.to_string(), AggFn::Sum),
+        })
+        .collect()
+}
+
+impl PipelineState {
+    /// Arma el pipeline para una tarea: busca en `dag` los nodos cuyo `id`
+    /// aparece en `node_id_chain` (separados por ">") y los deja en el
+    /// orden topológico del DAG completo.
+    pub fn new(
+        dag: &Dag,
+        node_id_chain: &str,
+        input_path: &str,
+        output_path: &str,
+    ) -> io::Result<Self> {
+        let order = crate::dag::topo_sort(dag);
+        let by_id: HashMap<&str, &DagNode> =
+            dag.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+        let wanted: HashSet<&str> = node_id_chain.split('>').collect();
+
+        let nodes: Vec<DagNode> = order
+            .into_iter()
+            .filter(|id| wanted.contains(id.as_str()))
+            .filter_map(|id| by_id.get(id.as_str()).map(|n| (*n).clone()))
+            .collect();
+
+        if nodes.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "no se encontró ningún nodo del DAG para la cadena '{}'",
+                    node_id_chain
+                ),
+            ));
+        }
+
+        let total_input_bytes = input_path
+            .split(',')
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .filter_map(|p| fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum();
+
+        Ok(Self {
+            nodes,
+            input_path: input_path.to_string(),
+            output_path: output_path.to_string(),
+            cursor: 0,
+            seeded: false,
+            current: Records::new(),
+            buf: Records::new(),
+            sub_cursor: 0,
+            done: false,
+            lua_udfs: HashMap::new(),
+            total_input_bytes,
+        })
+    }
+
+    /// Avance incremental aproximado para reportarle al worker (ver
+    /// `worker::ActiveTask::step` y `POST /api/v1/tasks/{id}/progress`):
+    /// cada nodo del pipeline pesa lo mismo, y dentro de un nodo angosto
+    /// en curso se pondera por cuántos registros del batch ya se
+    /// consumieron. Es una aproximación -- no sabemos cuánto "cuesta" cada
+    /// nodo en bytes -- pero alcanza para una barra de progreso que no se
+    /// quede clavada en 0% durante todo el pipeline.
+    ///
+    /// Devuelve `(processed_bytes, total_bytes, processed_records)`.
+    pub fn progress(&self) -> (u64, u64, u64) {
+        let total_nodes = self.nodes.len().max(1) as f32;
+
+        let fraction = if self.done {
+            1.0
+        } else if !self.seeded {
+            0.0
+        } else {
+            let node_fraction = if self.current.is_empty() {
+                0.0
+            } else {
+                (self.sub_cursor as f32 / self.current.len() as f32).clamp(0.0, 1.0)
+            };
+            ((self.cursor as f32 + node_fraction) / total_nodes).clamp(0.0, 1.0)
+        };
+
+        let processed_bytes = (self.total_input_bytes as f32 * fraction) as u64;
+
+        // `sub_cursor` sólo tiene sentido dentro del nodo angosto actual,
+        // así que como conteo de "registros procesados" reportamos eso en
+        // vez de intentar sumarlo a través de nodos con tamaños distintos
+        // (filter/flat_map cambian la cantidad de registros en cada paso).
+        (processed_bytes, self.total_input_bytes, self.sub_cursor as u64)
+    }
+
+    /// Devuelve la UDF Lua compilada para `node_id`, compilándola la
+    /// primera vez que se la necesita y reusándola en los quanta siguientes.
+    fn udf_for_node(&mut self, node_id: &str, src: &str) -> io::Result<LuaUdf> {
+        if !self.lua_udfs.contains_key(node_id) {
+            let udf = LuaUdf::compile(src).map_err(lua_err)?;
+            self.lua_udfs.insert(node_id.to_string(), udf);
+        }
+        Ok(self.lua_udfs.get(node_id).unwrap().clone())
+    }
+
+    /// Avanza el pipeline un "quantum" de trabajo: carga la entrada, procesa
+    /// hasta `RECORDS_PER_QUANTUM` registros de un operador angosto, o
+    /// ejecuta de punta a punta un operador ancho (reduce_by_key/join),
+    /// según en qué nodo esté parado el cursor.
+    ///
+    /// Devuelve `Ok(true)` cuando ya se escribió la salida final.
+    pub fn step(&mut self) -> io::Result<bool> {
+        if self.done {
+            return Ok(true);
+        }
+
+        if !self.seeded {
+            let first_op = self.nodes[0].op.clone();
+            self.current = if first_op.starts_with("read_") {
+                let recs = read_source_records(&first_op, &self.input_path)?;
+                self.cursor = 1;
+                recs
+            } else {
+                read_shuffled_records(&self.input_path)?
+            };
+            self.seeded = true;
+        } else if self.cursor < self.nodes.len() {
+            let node = self.nodes[self.cursor].clone();
+            match node.op.as_str() {
+                "map" | "filter" | "flat_map" | "distinct" | "sort_by" | "is_in" => {
+                    self.step_narrow(&node)?
+                }
+                "reduce_by_key" => self.step_reduce_by_key(&node)?,
+                "aggregate_by_key" => self.step_aggregate_by_key(&node)?,
+                "join" => self.step_join(&node)?,
+                other => {
+                    eprintln!(
+                        "[engine] operador desconocido '{}' en nodo '{}', se deja el stream sin cambios",
+                        other, node.id
+                    );
+                    self.cursor += 1;
+                }
+            }
+        }
+
+        if self.cursor >= self.nodes.len() {
+            self.flush_output()?;
+            self.done = true;
+        }
+
+        Ok(self.done)
+    }
+
+    fn step_narrow(&mut self, node: &DagNode) -> io::Result<()> {
+        // `distinct`/`sort_by` necesitan ver toda la partición junta (el
+        // dedup o el orden no se pueden calcular de a un quantum de
+        // RECORDS_PER_QUANTUM registros sin romper la semántica: un
+        // registro "duplicado" o "fuera de orden" contra uno de otro
+        // quantum no se detectaría), así que para esos dos nodos el
+        // "chunk" de este quantum es toda la entrada que falte procesar.
+        let whole_node = matches!(node.op.as_str(), "distinct" | "sort_by");
+        let end = if whole_node {
+            self.current.len()
+        } else {
+            (self.sub_cursor + RECORDS_PER_QUANTUM).min(self.current.len())
+        };
+        let chunk: Records = self.current[self.sub_cursor..end].to_vec();
+
+        let mut produced = if let Some(src) = node.fn_src.clone() {
+            let udf = self.udf_for_node(&node.id, &src)?;
+            match node.op.as_str() {
+                "map" => {
+                    let mut out = Records::new();
+                    for rec in &chunk {
+                        out.push(udf.call_map(rec).map_err(lua_err)?);
+                    }
+                    out
+                }
+                "filter" => {
+                    let mut out = Records::new();
+                    for rec in chunk.into_iter() {
+                        if udf.call_filter(&rec).map_err(lua_err)? {
+                            out.push(rec);
+                        }
+                    }
+                    out
+                }
+                "flat_map" => {
+                    let mut out = Records::new();
+                    for rec in &chunk {
+                        out.extend(udf.call_flat_map(rec).map_err(lua_err)?);
+                    }
+                    out
+                }
+                _ => chunk,
+            }
+        } else {
+            match node.op.as_str() {
+                "map" => {
+                    let fn_name = node.fn_name.as_deref().unwrap_or("");
+                    op_map(chunk, |r| apply_named_map(fn_name, r))
+                }
+                "filter" => {
+                    let fn_name = node.fn_name.as_deref().unwrap_or("");
+                    op_filter(chunk, |r| apply_named_filter(fn_name, r))
+                }
+                "flat_map" => {
+                    let fn_name = node.fn_name.as_deref().unwrap_or("");
+                    op_flat_map(chunk, |r| apply_named_flat_map(fn_name, r))
+                }
+                "distinct" => {
+                    let key_field = node.key.as_deref().unwrap_or("key");
+                    op_distinct(chunk, key_field)
+                }
+                "sort_by" => {
+                    let fields = parse_sort_spec(node.fn_name.as_deref().unwrap_or(""));
+                    let fields: Vec<(&str, SortOrder)> =
+                        fields.iter().map(|(f, o)| (f.as_str(), *o)).collect();
+                    op_sort_by(chunk, &fields)
+                }
+                "is_in" => {
+                    let field = node.key.as_deref().unwrap_or("key");
+                    let values = parse_value_set(node.fn_name.as_deref().unwrap_or(""));
+                    op_is_in(chunk, field, &values)
+                }
+                _ => chunk,
+            }
+        };
+
+        self.buf.append(&mut produced);
+        self.sub_cursor = end;
+
+        if self.sub_cursor >= self.current.len() {
+            self.current = std::mem::take(&mut self.buf);
+            self.sub_cursor = 0;
+            self.cursor += 1;
+        }
+
+        Ok(())
+    }
+
+    fn step_reduce_by_key(&mut self, node: &DagNode) -> io::Result<()> {
+        let key_field = node.key.clone().unwrap_or_else(|| "key".to_string());
+
+        if let Some(src) = node.fn_src.clone() {
+            let udf = self.udf_for_node(&node.id, &src)?;
+
+            // Orden determinista: primera aparición de cada clave. El
+            // acumulador arranca en el primer registro de la clave (no hay
+            // un valor de init por separado declarado en el DAG).
+            let mut acc_by_key: HashMap<String, Record> = HashMap::new();
+            let mut key_order: Vec<String> = Vec::new();
+
+            for rec in std::mem::take(&mut self.current).into_iter() {
+                let Some(k) = rec.get(&key_field).and_then(|v| v.as_str()).map(|s| s.to_string())
+                else {
+                    continue;
+                };
+
+                if let Some(acc) = acc_by_key.get(&k) {
+                    let new_acc = udf.call_reduce(acc, &rec).map_err(lua_err)?;
+                    acc_by_key.insert(k, new_acc);
+                } else {
+                    key_order.push(k.clone());
+                    acc_by_key.insert(k, rec);
+                }
+            }
+
+            self.current = key_order
+                .into_iter()
+                .filter_map(|k| acc_by_key.remove(&k))
+                .collect();
+            self.cursor += 1;
+            return Ok(());
+        }
+
+        let agg = aggregator_for(node.fn_name.as_deref().unwrap_or("sum"));
+        self.current = op_reduce_by_key_with(
+            std::mem::take(&mut self.current),
+            &key_field,
+            "count",
+            agg.as_ref(),
+        );
+        self.cursor += 1;
+        Ok(())
+    }
+
+    /// aggregate_by_key: agrupa por `node.key` y calcula una o más
+    /// agregaciones por campo, con `node.fn_name` codificando la lista de
+    /// specs como "campo:fn,campo2:fn2" (ver `parse_agg_specs`). Agrupa
+    /// por clave igual que `reduce_by_key`, así que necesita el mismo
+    /// shuffle entre etapas: `master/src/schedule.rs` lo trata como
+    /// operador ancho (`WIDE_OPS`).
+    fn step_aggregate_by_key(&mut self, node: &DagNode) -> io::Result<()> {
+        let key_field = node.key.clone().unwrap_or_else(|| "key".to_string());
+        let specs = parse_agg_specs(node.fn_name.as_deref().unwrap_or(""));
+        let specs: Vec<(&str, AggFn)> = specs.iter().map(|(f, k)| (f.as_str(), *k)).collect();
+
+        self.current = op_aggregate_by_key(std::mem::take(&mut self.current), &key_field, &specs);
+        self.cursor += 1;
+        Ok(())
+    }
+
+    fn step_join(&mut self, node: &DagNode) -> io::Result<()> {
+        let key_field = node.key.clone().unwrap_or_else(|| "id".to_string());
+        let join_type = node
+            .fn_name
+            .as_deref()
+            .map(join_type_for)
+            .unwrap_or(JoinType::Inner);
+
+        match &node.path {
+            Some(side_path) => {
+                let side_records = read_side_input(side_path)?;
+                self.current = op_join_by_key_with(
+                    std::mem::take(&mut self.current),
+                    side_records,
+                    &key_field,
+                    join_type,
+                );
+            }
+            None => {
+                eprintln!(
+                    "[engine] nodo join '{}' sin `path` de entrada lateral: se deja el stream sin cambios",
+                    node.id
+                );
+            }
+        }
+
+        self.cursor += 1;
+        Ok(())
+    }
+
+    fn flush_output(&mut self) -> io::Result<()> {
+        if let Some(parent) = Path::new(&self.output_path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let last = self.nodes.last();
+        let file = File::create(&self.output_path)?;
+        let mut writer = BufWriter::new(file);
+
+        if let Some(node) = last.filter(|n| n.op == "reduce_by_key" && n.fn_src.is_none()) {
+            // Convención del resto del motor: reduce_by_key termina en un
+            // CSV "clave,valor" (ver `reduce_partitions_to_file`). Si el
+            // nodo usa una UDF Lua el acumulador puede tener cualquier
+            // forma, así que esos casos caen al JSONL genérico de abajo.
+            let key_field = node.key.clone().unwrap_or_else(|| "key".to_string());
+            for rec in &self.current {
+                let k = rec.get(&key_field).and_then(|v| v.as_str()).unwrap_or_default();
+                let v = rec.get("count").cloned().unwrap_or(Value::Null);
+                writeln!(
+                    writer,
+                    "{},{}",
+                    csv_quote_field(k, ',', '"'),
+                    csv_quote_field(&format_value_for_csv(&v), ',', '"')
+                )?;
+            }
+        } else {
+            for rec in &self.current {
+                serde_json::to_writer(&mut writer, rec)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -935,58 +3637,180 @@ mod tests {
     #[test]
     fn op_filter_filtra_por_predicado() {
         let input = vec![
-            json!({"x": 1}),
-            json!({"x": 2}),
-            json!({"x": 3}),
+            json!({"x": 1}),
+            json!({"x": 2}),
+            json!({"x": 3}),
+        ];
+
+        let out = op_filter(input, |r| r["x"].as_i64().unwrap() % 2 == 1);
+
+        assert_eq!(out, vec![json!({"x": 1}), json!({"x": 3})]);
+    }
+
+    #[test]
+    fn op_flat_map_expande_registros() {
+        let input = vec![
+            json!({"nums": [1, 2]}),
+            json!({"nums": [3]}),
+        ];
+
+        let out = op_flat_map(input, |r| {
+            r["nums"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|n| json!({"n": n}))
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(
+            out,
+            vec![
+                json!({"n": 1}),
+                json!({"n": 2}),
+                json!({"n": 3}),
+            ]
+        );
+    }
+
+    #[test]
+    fn op_reduce_by_key_agrupa_y_suma() {
+        let input = vec![
+            json!({"token": "a", "count": 1_u64}),
+            json!({"token": "b", "count": 1_u64}),
+            json!({"token": "a", "count": 2_u64}),
+        ];
+
+        let out = op_reduce_by_key(input, "token", "count");
+
+        // reduce_by_key ordena por clave
+        assert_eq!(
+            out,
+            vec![
+                json!({"token": "a", "count": 3_u64}),
+                json!({"token": "b", "count": 1_u64}),
+            ]
+        );
+    }
+
+    #[test]
+    fn op_distinct_deja_un_registro_por_clave_y_ordena() {
+        let input = vec![
+            json!({"token": "b", "count": 1_u64}),
+            json!({"token": "a", "count": 1_u64}),
+            json!({"token": "a", "count": 99_u64}),
+        ];
+
+        let out = op_distinct(input, "token");
+
+        assert_eq!(
+            out,
+            vec![
+                json!({"token": "a", "count": 1_u64}),
+                json!({"token": "b", "count": 1_u64}),
+            ]
+        );
+    }
+
+    #[test]
+    fn op_sort_by_ordena_por_varias_claves() {
+        let input = vec![
+            json!({"grupo": "b", "n": 2}),
+            json!({"grupo": "a", "n": 2}),
+            json!({"grupo": "a", "n": 1}),
+        ];
+
+        let out = op_sort_by(input, &[("grupo", SortOrder::Asc), ("n", SortOrder::Desc)]);
+
+        assert_eq!(
+            out,
+            vec![
+                json!({"grupo": "a", "n": 2}),
+                json!({"grupo": "a", "n": 1}),
+                json!({"grupo": "b", "n": 2}),
+            ]
+        );
+    }
+
+    #[test]
+    fn op_is_in_filtra_por_conjunto_de_valores() {
+        let input = vec![
+            json!({"token": "a"}),
+            json!({"token": "b"}),
+            json!({"token": "c"}),
         ];
 
-        let out = op_filter(input, |r| r["x"].as_i64().unwrap() % 2 == 1);
+        let mut values = HashSet::new();
+        values.insert("a".to_string());
+        values.insert("c".to_string());
 
-        assert_eq!(out, vec![json!({"x": 1}), json!({"x": 3})]);
+        let out = op_is_in(input, "token", &values);
+
+        assert_eq!(out, vec![json!({"token": "a"}), json!({"token": "c"})]);
     }
 
     #[test]
-    fn op_flat_map_expande_registros() {
+    fn op_aggregate_by_key_calcula_varias_agregaciones_en_una_pasada() {
+        // Specs sobre campos distintos: cada uno se agrega con su propia
+        // AggFn en la misma pasada sobre `input`.
         let input = vec![
-            json!({"nums": [1, 2]}),
-            json!({"nums": [3]}),
+            json!({"token": "a", "count": 1_u64, "score": 10.0, "tag": "x"}),
+            json!({"token": "b", "count": 1_u64, "score": 5.0, "tag": "y"}),
+            json!({"token": "a", "count": 3_u64, "score": 20.0, "tag": "z"}),
         ];
 
-        let out = op_flat_map(input, |r| {
-            r["nums"]
-                .as_array()
-                .unwrap()
-                .iter()
-                .map(|n| json!({"n": n}))
-                .collect::<Vec<_>>()
-        });
+        let out = op_aggregate_by_key(
+            input,
+            "token",
+            &[
+                ("count", AggFn::Sum),
+                ("score", AggFn::Max),
+                ("tag", AggFn::CollectList),
+            ],
+        );
 
         assert_eq!(
             out,
             vec![
-                json!({"n": 1}),
-                json!({"n": 2}),
-                json!({"n": 3}),
+                json!({
+                    "token": "a",
+                    "count": 4_u64,
+                    "score": 20.0,
+                    "tag": ["x", "z"],
+                }),
+                json!({
+                    "token": "b",
+                    "count": 1_u64,
+                    "score": 5.0,
+                    "tag": ["y"],
+                }),
             ]
         );
     }
 
     #[test]
-    fn op_reduce_by_key_agrupa_y_suma() {
+    fn op_aggregate_by_key_count_y_avg() {
         let input = vec![
-            json!({"token": "a", "count": 1_u64}),
-            json!({"token": "b", "count": 1_u64}),
-            json!({"token": "a", "count": 2_u64}),
+            json!({"token": "a", "score": 10.0}),
+            json!({"token": "a", "score": 20.0}),
+            json!({"token": "b", "score": 5.0}),
         ];
 
-        let out = op_reduce_by_key(input, "token", "count");
+        let out = op_aggregate_by_key(
+            input,
+            "token",
+            &[("score", AggFn::Count), ("score", AggFn::Avg)],
+        );
 
-        // reduce_by_key ordena por clave
+        // Ambos specs comparten el campo "score": como el nombre de
+        // salida es el nombre del campo de entrada, el último spec pisa
+        // al anterior en el registro de salida (limitación conocida de
+        // no tener un nombre de salida separado por spec).
         assert_eq!(
             out,
             vec![
-                json!({"token": "a", "count": 3_u64}),
-                json!({"token": "b", "count": 1_u64}),
+                json!({"token": "a", "score": 15.0}),
+                json!({"token": "b", "score": 5.0}),
             ]
         );
     }
@@ -1012,6 +3836,94 @@ mod tests {
         assert_eq!(rec["compras"], json!(10));
     }
 
+    #[test]
+    fn op_join_by_key_with_left_outer_completa_sin_match_con_null() {
+        let left = vec![
+            json!({"id": "u1", "nombre": "Ana"}),
+            json!({"id": "u2", "nombre": "Bob"}),
+        ];
+        let right = vec![json!({"id": "u1", "compras": 10})];
+
+        let out = op_join_by_key_with(left, right, "id", JoinType::LeftOuter);
+        assert_eq!(out.len(), 2);
+
+        let u1 = out.iter().find(|r| r["id"] == json!("u1")).unwrap();
+        assert_eq!(u1["compras"], json!(10));
+
+        let u2 = out.iter().find(|r| r["id"] == json!("u2")).unwrap();
+        assert_eq!(u2["nombre"], json!("Bob"));
+        assert_eq!(u2["compras"], Value::Null);
+    }
+
+    #[test]
+    fn op_join_by_key_with_right_outer_completa_sin_match_con_null() {
+        let left = vec![json!({"id": "u1", "nombre": "Ana"})];
+        let right = vec![
+            json!({"id": "u1", "compras": 10}),
+            json!({"id": "u3", "compras": 99}),
+        ];
+
+        let out = op_join_by_key_with(left, right, "id", JoinType::RightOuter);
+        assert_eq!(out.len(), 2);
+
+        let u1 = out.iter().find(|r| r["id"] == json!("u1")).unwrap();
+        assert_eq!(u1["nombre"], json!("Ana"));
+        assert_eq!(u1["compras"], json!(10));
+
+        let u3 = out.iter().find(|r| r["id"] == json!("u3")).unwrap();
+        assert_eq!(u3["compras"], json!(99));
+        assert_eq!(u3["nombre"], Value::Null);
+    }
+
+    #[test]
+    fn op_join_by_key_with_full_outer_junta_ambos_lados_sin_match() {
+        let left = vec![
+            json!({"id": "u1", "nombre": "Ana"}),
+            json!({"id": "u2", "nombre": "Bob"}),
+        ];
+        let right = vec![
+            json!({"id": "u1", "compras": 10}),
+            json!({"id": "u3", "compras": 99}),
+        ];
+
+        let out = op_join_by_key_with(left, right, "id", JoinType::FullOuter);
+        assert_eq!(out.len(), 3);
+        assert!(out.iter().any(|r| r["id"] == json!("u1") && r["compras"] == json!(10)));
+        assert!(out.iter().any(|r| r["id"] == json!("u2") && r["compras"] == Value::Null));
+        assert!(out.iter().any(|r| r["id"] == json!("u3") && r["nombre"] == Value::Null));
+    }
+
+    #[test]
+    fn op_join_by_key_with_left_semi_no_duplica_y_no_fusiona() {
+        let left = vec![
+            json!({"id": "u1", "nombre": "Ana"}),
+            json!({"id": "u2", "nombre": "Bob"}),
+        ];
+        let right = vec![
+            json!({"id": "u1", "compras": 10}),
+            json!({"id": "u1", "compras": 20}),
+        ];
+
+        let out = op_join_by_key_with(left, right, "id", JoinType::LeftSemi);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0]["id"], json!("u1"));
+        assert_eq!(out[0]["nombre"], json!("Ana"));
+        assert!(out[0].get("compras").is_none());
+    }
+
+    #[test]
+    fn op_join_by_key_with_left_anti_solo_deja_sin_match() {
+        let left = vec![
+            json!({"id": "u1", "nombre": "Ana"}),
+            json!({"id": "u2", "nombre": "Bob"}),
+        ];
+        let right = vec![json!({"id": "u1", "compras": 10})];
+
+        let out = op_join_by_key_with(left, right, "id", JoinType::LeftAnti);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0]["id"], json!("u2"));
+    }
+
     #[test]
     fn merge_records_respeta_campos_izquierda_y_prefija_derecha() {
         let left = json!({"id": "u1", "x": 1, "compartido": "L"});
@@ -1027,6 +3939,23 @@ mod tests {
         assert_eq!(merged["right_compartido"], json!("R"));
     }
 
+    #[test]
+    fn op_join_by_key_acepta_jsonpath_anidado_como_clave() {
+        let left = vec![
+            json!({"user": {"id": "u1"}, "nombre": "Ana"}),
+            json!({"user": {"id": "u2"}, "nombre": "Bob"}),
+        ];
+        let right = vec![json!({"user": {"id": "u1"}, "compras": 10})];
+
+        let out = op_join_by_key(left, right, "$.user.id");
+        assert_eq!(out.len(), 1);
+
+        let rec = &out[0];
+        assert_eq!(rec["user"]["id"], json!("u1"));
+        assert_eq!(rec["nombre"], json!("Ana"));
+        assert_eq!(rec["compras"], json!(10));
+    }
+
     /* =========================
        WORDCOUNT - HELPERS/PIPES
        ========================= */
@@ -1071,6 +4000,16 @@ mod tests {
         assert_eq!(acc.get("mundo"), Some(&1));
     }
 
+    #[test]
+    fn wc_stage1_from_records_acepta_jsonpath_anidado() {
+        let input = vec![json!({"body": {"text": "hola mundo"}})];
+
+        let recs = super::wc_stage1_from_records(input, "$.body.text");
+        let tokens: Vec<&str> = recs.iter().map(|r| r["token"].as_str().unwrap()).collect();
+
+        assert_eq!(tokens, vec!["hola", "mundo"]);
+    }
+
     #[test]
     fn wordcount_from_lines_cuenta_tokens_correctamente() {
         let lines = vec!["hola hola mundo", "mundo a"];
@@ -1099,6 +4038,61 @@ mod tests {
         assert_eq!(simple, via_ops);
     }
 
+    #[test]
+    fn default_tokenizer_nfc_unifica_precompuesto_y_combinante() {
+        let tokenizer = DefaultTokenizer::default();
+
+        // "café" con tilde precompuesta (U+00E9) vs. con tilde combinante
+        // (U+0065 U+0301): deben normalizar al mismo token.
+        let precompuesto = tokenizer.tokenize("café");
+        let combinante = tokenizer.tokenize("cafe\u{0301}");
+
+        assert_eq!(precompuesto, vec!["café".to_string()]);
+        assert_eq!(precompuesto, combinante);
+    }
+
+    #[test]
+    fn default_tokenizer_respeta_stopwords_y_min_len() {
+        let mut stopwords = HashSet::new();
+        stopwords.insert("de".to_string());
+
+        let tokenizer = DefaultTokenizer::new(TokenizerConfig {
+            normalization: NormalizationForm::Nfc,
+            stopwords,
+            min_len: 2,
+        });
+
+        let tokens = tokenizer.tokenize("el dia de hoy a las 5");
+
+        assert_eq!(
+            tokens,
+            vec!["el".to_string(), "dia".to_string(), "hoy".to_string(), "las".to_string()]
+        );
+    }
+
+    #[test]
+    fn wordcount_from_lines_with_tokenizer_usa_config_custom() {
+        let mut stopwords = HashSet::new();
+        stopwords.insert("a".to_string());
+
+        let tokenizer = DefaultTokenizer::new(TokenizerConfig {
+            normalization: NormalizationForm::Nfc,
+            stopwords,
+            min_len: 1,
+        });
+
+        let lines = vec!["hola hola mundo", "mundo a"];
+        let out = wordcount_from_lines_with_tokenizer(lines, &tokenizer);
+
+        assert_eq!(
+            out,
+            vec![
+                json!({"token": "hola", "count": 2_u64}),
+                json!({"token": "mundo", "count": 2_u64}),
+            ]
+        );
+    }
+
     /* =========================
        IO: CSV / JSONL / PARTITIONS
        ========================= */
@@ -1154,7 +4148,7 @@ mod tests {
         writeln!(f, "{}", r#"{"k":"a","v":1}"#).unwrap();
         writeln!(f, "{}", r#"{"k":"b","v":2}"#).unwrap();
 
-        let recs = read_partition(p_path.to_str().unwrap()).unwrap();
+        let recs = read_partition(p_path.to_str().unwrap(), PartitionFormat::Jsonl).unwrap();
         assert_eq!(recs.len(), 2);
         assert_eq!(recs[0]["k"], json!("a"));
         assert_eq!(recs[1]["v"], json!(2));
@@ -1205,6 +4199,34 @@ mod tests {
         assert_eq!(lines, vec!["a,2", "b,1"]);
     }
 
+    #[test]
+    fn shuffle_to_partitions_preserva_tipos_numericos_via_formato_binario() {
+        // `shuffle_to_partitions` escribe en `PartitionFormat::Binary`
+        // (rmp-serde), a diferencia de las particiones JSONL de texto: un
+        // entero no debería volver como string al leerlo de vuelta.
+        let tmp = temp_dir("shuffle_binary_types");
+        let tmp_str = tmp.to_string_lossy().to_string();
+
+        let input = vec![
+            json!({"token": "a", "count": 3_i64}),
+            json!({"token": "b", "count": 5_i64}),
+        ];
+
+        let parts = shuffle_to_partitions(input, "token", 2, &tmp_str, "stage_types").unwrap();
+        assert!(parts.iter().all(|p| p.format == PartitionFormat::Binary));
+
+        let mut recs: Records = Vec::new();
+        for p in &parts {
+            recs.extend(read_partition(&p.path, p.format).unwrap());
+        }
+        recs.sort_by_key(|r| r["token"].as_str().unwrap().to_string());
+
+        assert_eq!(recs[0]["count"], json!(3));
+        assert!(recs[0]["count"].is_number());
+        assert_eq!(recs[1]["count"], json!(5));
+        assert!(recs[1]["count"].is_number());
+    }
+
     #[test]
     fn reduce_partitions_to_file_con_lista_vacia_crea_archivo_vacio() {
         let tmp = temp_dir("reduce_empty");
@@ -1229,6 +4251,63 @@ mod tests {
         env::remove_var("MAX_IN_MEM_KEYS");
     }
 
+    #[test]
+    fn max_concurrency_respeta_env_var() {
+        env::set_var("MINISPARK_MAX_CONCURRENCY", "3");
+        assert_eq!(super::max_concurrency(), 3);
+        env::remove_var("MINISPARK_MAX_CONCURRENCY");
+    }
+
+    #[test]
+    fn max_concurrency_ignora_valores_invalidos_o_cero() {
+        env::set_var("MINISPARK_MAX_CONCURRENCY", "0");
+        assert!(super::max_concurrency() >= 1);
+        env::remove_var("MINISPARK_MAX_CONCURRENCY");
+    }
+
+    #[test]
+    fn sequential_mode_respeta_env_var() {
+        assert!(!super::sequential_mode());
+        env::set_var("MINISPARK_SEQUENTIAL", "1");
+        assert!(super::sequential_mode());
+        env::remove_var("MINISPARK_SEQUENTIAL");
+    }
+
+    #[test]
+    fn chunk_size_for_reparte_parejo_y_nunca_da_cero() {
+        assert_eq!(super::chunk_size_for(10, 3), 4); // ceil(10/3)
+        assert_eq!(super::chunk_size_for(0, 4), 1);
+        assert_eq!(super::chunk_size_for(5, 0), 5); // workers=0 se trata como 1
+    }
+
+    #[test]
+    fn reduce_partitions_to_file_con_concurrencia_acotada_da_el_mismo_resultado() {
+        let tmp = temp_dir("reduce_parallel");
+        let tmp_str = tmp.to_string_lossy().to_string();
+
+        let input = vec![
+            json!({"token": "a", "count": 1_u64}),
+            json!({"token": "b", "count": 1_u64}),
+            json!({"token": "a", "count": 1_u64}),
+            json!({"token": "c", "count": 1_u64}),
+        ];
+
+        let parts = shuffle_to_partitions(input, "token", 4, &tmp_str, "stage_test").unwrap();
+
+        let out_path = tmp.join("out.csv");
+        let out_str = out_path.to_string_lossy().to_string();
+
+        env::set_var("MINISPARK_MAX_CONCURRENCY", "2");
+        reduce_partitions_to_file(&parts, "token", "count", &out_str).unwrap();
+        env::remove_var("MINISPARK_MAX_CONCURRENCY");
+
+        let content = fs::read_to_string(out_path).unwrap();
+        let mut lines: Vec<&str> = content.lines().collect();
+        lines.sort();
+
+        assert_eq!(lines, vec!["a,2", "b,1", "c,1"]);
+    }
+
     #[test]
     fn spilling_aggregator_spillea_y_finaliza_correctamente() {
         let tmp = temp_dir("spill");
@@ -1236,11 +4315,12 @@ mod tests {
         let dir_str = dir.to_string_lossy().to_string();
 
         // threshold = 2 => al insertar la segunda clave se hace spill
-        let mut agg = super::SpillingAggregator::new(&dir_str, 2).unwrap();
+        let mut agg =
+            super::SpillingAggregator::new(&dir_str, 2, super::aggregator_for("sum")).unwrap();
 
-        agg.add("a", 1).unwrap(); // mapa: {a:1}
-        agg.add("b", 1).unwrap(); // mapa alcanza threshold => spill; se limpia
-        agg.add("a", 2).unwrap(); // mapa: {a:2}
+        agg.add("a", &json!(1)).unwrap(); // mapa: {a:1}
+        agg.add("b", &json!(1)).unwrap(); // mapa alcanza threshold => spill; se limpia
+        agg.add("a", &json!(2)).unwrap(); // mapa: {a:2}
 
         let out_path = tmp.join("final.csv");
         let out_str = out_path.to_string_lossy().to_string();
@@ -1366,10 +4446,12 @@ mod tests {
         let left_parts = vec![Partition {
             id: 0,
             path: left_path.to_string_lossy().to_string(),
+            format: PartitionFormat::Jsonl,
         }];
         let right_parts = vec![Partition {
             id: 0,
             path: right_path.to_string_lossy().to_string(),
+            format: PartitionFormat::Jsonl,
         }];
 
         let out_path = tmp.join("join.jsonl");
@@ -1423,6 +4505,176 @@ mod tests {
         assert_eq!(rec["nombre"], json!("Ana"));
         assert_eq!(rec["compras"], json!("10")); // se leyó como string desde CSV
     }
+
+    /* =========================
+       INTÉRPRETE GENÉRICO DE DAGs
+       ========================= */
+
+    fn dag_node(id: &str, op: &str, fn_name: Option<&str>, key: Option<&str>) -> DagNode {
+        DagNode {
+            id: id.to_string(),
+            op: op.to_string(),
+            path: None,
+            partitions: None,
+            fn_name: fn_name.map(|s| s.to_string()),
+            key: key.map(|s| s.to_string()),
+            fn_src: None,
+        }
+    }
+
+    #[test]
+    fn pipeline_state_ejecuta_wordcount_generico_igual_que_la_version_fija() {
+        let tmp = temp_dir("pipeline_wordcount");
+        let input_path = tmp.join("input.txt");
+        let mut f = fs::File::create(&input_path).unwrap();
+        writeln!(f, "Hola HOLA mundo").unwrap();
+        writeln!(f, "mundo mundo prueba").unwrap();
+
+        let dag = Dag {
+            nodes: vec![
+                dag_node("read", "read_text", None, None),
+                dag_node("flat", "flat_map", Some("tokenize"), None),
+                dag_node("map1", "map", Some("to_lower"), None),
+                dag_node("agg", "reduce_by_key", Some("sum"), Some("token")),
+            ],
+            edges: vec![
+                ("read".into(), "flat".into()),
+                ("flat".into(), "map1".into()),
+                ("map1".into(), "agg".into()),
+            ],
+        };
+
+        let out_path = tmp.join("out.csv");
+        let mut state = PipelineState::new(
+            &dag,
+            "read>flat>map1>agg",
+            input_path.to_str().unwrap(),
+            out_path.to_str().unwrap(),
+        )
+        .unwrap();
+
+        // avanzamos hasta que el pipeline se declare terminado
+        let mut iterations = 0;
+        while !state.step().unwrap() {
+            iterations += 1;
+            assert!(iterations < 1000, "el pipeline no debería tardar tanto en terminar");
+        }
+
+        let content = fs::read_to_string(out_path).unwrap();
+        let mut lines: Vec<&str> = content.lines().collect();
+        lines.sort();
+
+        // hola x2, mundo x3, prueba x1
+        assert_eq!(lines, vec!["hola,2", "mundo,3", "prueba,1"]);
+    }
+
+    #[test]
+    fn pipeline_state_ejecuta_join_contra_un_path_lateral() {
+        let tmp = temp_dir("pipeline_join");
+
+        let left_path = tmp.join("ventas.csv");
+        let mut lf = fs::File::create(&left_path).unwrap();
+        writeln!(lf, "id,monto").unwrap();
+        writeln!(lf, "u1,100").unwrap();
+        writeln!(lf, "u2,200").unwrap();
+
+        let right_path = tmp.join("catalogo.csv");
+        let mut rf = fs::File::create(&right_path).unwrap();
+        writeln!(rf, "id,nombre").unwrap();
+        writeln!(rf, "u1,Ana").unwrap();
+
+        let mut join_node = dag_node("join1", "join", None, Some("id"));
+        join_node.path = Some(right_path.to_str().unwrap().to_string());
+
+        let dag = Dag {
+            nodes: vec![dag_node("read", "read_csv", None, None), join_node],
+            edges: vec![("read".into(), "join1".into())],
+        };
+
+        let out_path = tmp.join("out.jsonl");
+        let mut state = PipelineState::new(
+            &dag,
+            "read>join1",
+            left_path.to_str().unwrap(),
+            out_path.to_str().unwrap(),
+        )
+        .unwrap();
+
+        while !state.step().unwrap() {}
+
+        let content = fs::read_to_string(out_path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 1);
+
+        let rec: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(rec["id"], json!("u1"));
+        assert_eq!(rec["nombre"], json!("Ana"));
+    }
+
+    #[test]
+    fn pipeline_state_falla_si_no_encuentra_nodos_para_la_cadena() {
+        let dag = Dag {
+            nodes: vec![dag_node("read", "read_text", None, None)],
+            edges: vec![],
+        };
+
+        let res = PipelineState::new(&dag, "no_existe", "in.txt", "out.jsonl");
+        assert!(res.is_err());
+    }
+
+    /* =========================
+       UDFs LUA (DagNode.fn_src)
+       ========================= */
+
+    #[test]
+    fn lua_udf_map_duplica_un_campo_numerico() {
+        let udf = LuaUdf::compile("function(rec) rec.x = rec.x * 2; return rec end").unwrap();
+        let out = udf.call_map(&json!({"x": 21})).unwrap();
+        assert_eq!(out["x"], json!(42));
+    }
+
+    #[test]
+    fn pipeline_state_usa_fn_src_en_vez_de_fn_name_cuando_esta_presente() {
+        let tmp = temp_dir("pipeline_lua_map");
+        let jsonl_path = tmp.join("data.jsonl");
+        let mut f = fs::File::create(&jsonl_path).unwrap();
+        writeln!(f, "{}", r#"{"x": 1}"#).unwrap();
+        writeln!(f, "{}", r#"{"x": 2}"#).unwrap();
+
+        let mut map_node = dag_node("map1", "map", Some("to_lower"), None);
+        map_node.fn_src = Some("function(rec) rec.x = rec.x * 10; return rec end".to_string());
+
+        let dag = Dag {
+            nodes: vec![dag_node("read", "read_jsonl", None, None), map_node],
+            edges: vec![("read".into(), "map1".into())],
+        };
+
+        let out_path = tmp.join("out.jsonl");
+        let mut state = PipelineState::new(
+            &dag,
+            "read>map1",
+            jsonl_path.to_str().unwrap(),
+            out_path.to_str().unwrap(),
+        )
+        .unwrap();
+
+        while !state.step().unwrap() {}
+
+        let content = fs::read_to_string(out_path).unwrap();
+        let mut xs: Vec<i64> = content
+            .lines()
+            .map(|l| serde_json::from_str::<Value>(l).unwrap()["x"].as_i64().unwrap())
+            .collect();
+        xs.sort();
+
+        assert_eq!(xs, vec![10, 20]);
+    }
+
+    #[test]
+    fn lua_udf_con_error_de_sintaxis_falla_al_compilar() {
+        let res = LuaUdf::compile("esto no es Lua válido (((");
+        assert!(res.is_err());
+    }
 }
 
 
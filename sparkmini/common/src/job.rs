@@ -22,13 +22,20 @@ pub struct JobRequest {
     pub output_dir: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum JobStatus {
     Accepted,
     Running,
     Failed,
     Succeeded,
+    /// El operador lo pausó (`POST /api/v1/jobs/{id}/pause`): el scheduler
+    /// lo saltea al asignar tareas, pero sus tareas quedan donde estaban
+    /// en `tasks_queue` hasta que se reanude (`POST .../resume`).
+    Paused,
+    /// El operador lo canceló (`POST /api/v1/jobs/{id}/cancel`): se
+    /// descartan sus tareas pendientes y en vuelo, y no se reintenta más.
+    Cancelled,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,5 +61,15 @@ pub struct JobInfo {
     pub total_tasks: u32,
     pub completed_tasks: u32,
     pub failed_tasks: u32,
+
+    /// Cuántas veces se reintentó alguna tarea de este job.
     pub retries: u32,
+
+    /// Fracción completada del job (0.0-1.0), para que `client status`
+    /// pueda mostrar un porcentaje en vez de sólo RUNNING. Cuenta las
+    /// tareas ya terminadas como 1.0 cada una más, para las que están en
+    /// vuelo, la fracción que reportó su último
+    /// `POST /api/v1/tasks/{id}/progress` (ver `handlers::recompute_job_progress`).
+    #[serde(default)]
+    pub progress: f32,
 }
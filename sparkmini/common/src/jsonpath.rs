@@ -0,0 +1,148 @@
+//! Selectores de campo para registros anidados.
+//!
+//! Históricamente todo el engine indexa registros con un nombre de
+//! campo plano (`r["token"]`, `r[key_field]`). Los JSONL reales suelen
+//! venir anidados, así que acá agregamos un segundo modo: si el
+//! selector empieza con `$` se lo trata como una expresión JSONPath
+//! simple (`$.user.profile.email`, `$.events[0].type`) y se resuelve
+//! caminando el `Value`. Un selector que no empieza con `$` conserva el
+//! comportamiento plano de siempre.
+
+use serde_json::Value;
+
+/// Un segmento de un path ya compilado.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Compila una expresión JSONPath (con o sin el `$` inicial, que se
+/// descarta acá) en una lista de segmentos. Soporta el
+/// subconjunto que necesitamos: segmentos separados por `.` y
+/// subíndices `[n]`, pudiendo venir pegados (`events[0]`).
+fn compile(expr: &str) -> Vec<Segment> {
+    let expr = expr.strip_prefix('$').unwrap_or(expr);
+    let mut segments = Vec::new();
+
+    for part in expr.split('.') {
+        let mut rest = part;
+        while let Some(bracket_start) = rest.find('[') {
+            let name = &rest[..bracket_start];
+            if !name.is_empty() {
+                segments.push(Segment::Key(name.to_string()));
+            }
+
+            let tail = &rest[bracket_start + 1..];
+            let bracket_end = tail.find(']').unwrap_or(tail.len());
+            if let Ok(idx) = tail[..bracket_end].parse::<usize>() {
+                segments.push(Segment::Index(idx));
+            }
+
+            rest = tail.get(bracket_end + 1..).unwrap_or("");
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Key(rest.to_string()));
+        }
+    }
+
+    segments
+}
+
+/// Camina `value` siguiendo `path`. Devuelve `None` en cuanto un
+/// segmento no aplica (la clave no existe, o se pide un índice sobre
+/// algo que no es un arreglo, etc.).
+fn resolve<'a>(value: &'a Value, path: &[Segment]) -> Option<&'a Value> {
+    let mut current = value;
+    for seg in path {
+        current = match seg {
+            Segment::Key(k) => current.as_object()?.get(k)?,
+            Segment::Index(i) => current.as_array()?.get(*i)?,
+        };
+    }
+    Some(current)
+}
+
+/// `true` si `selector` es una expresión JSONPath (empieza con `$`) en
+/// vez de un nombre de campo plano.
+pub fn is_jsonpath(selector: &str) -> bool {
+    selector.starts_with('$')
+}
+
+/// Resuelve `selector` contra `record`: JSONPath si empieza con `$`,
+/// si no el acceso plano de siempre (`record[selector]`).
+pub fn get_selector<'a>(record: &'a Value, selector: &str) -> Option<&'a Value> {
+    if is_jsonpath(selector) {
+        resolve(record, &compile(selector))
+    } else {
+        record.as_object()?.get(selector)
+    }
+}
+
+/// Nombre plano a usar como clave de salida para `selector`: el
+/// selector tal cual si ya es un nombre plano, o el último segmento si
+/// es JSONPath (`"$.user.profile.email"` -> `"email"`). Un selector que
+/// termina en un índice de arreglo no tiene un nombre de campo natural,
+/// así que cae a `"item"`.
+pub fn leaf_name(selector: &str) -> String {
+    if !is_jsonpath(selector) {
+        return selector.to_string();
+    }
+    match compile(selector).last() {
+        Some(Segment::Key(k)) => k.clone(),
+        Some(Segment::Index(_)) | None => "item".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn get_selector_nombre_plano_indexa_directo() {
+        let rec = json!({"token": "hola", "count": 1});
+        assert_eq!(get_selector(&rec, "token"), Some(&json!("hola")));
+        assert_eq!(get_selector(&rec, "missing"), None);
+    }
+
+    #[test]
+    fn get_selector_jsonpath_resuelve_anidado() {
+        let rec = json!({"user": {"profile": {"email": "a@b.com"}}});
+        assert_eq!(
+            get_selector(&rec, "$.user.profile.email"),
+            Some(&json!("a@b.com"))
+        );
+    }
+
+    #[test]
+    fn get_selector_jsonpath_resuelve_indice_de_arreglo() {
+        let rec = json!({"events": [{"type": "click"}, {"type": "view"}]});
+        assert_eq!(
+            get_selector(&rec, "$.events[0].type"),
+            Some(&json!("click"))
+        );
+        assert_eq!(
+            get_selector(&rec, "$.events[1].type"),
+            Some(&json!("view"))
+        );
+    }
+
+    #[test]
+    fn get_selector_jsonpath_falla_limpio_si_no_existe() {
+        let rec = json!({"user": {"profile": {}}});
+        assert_eq!(get_selector(&rec, "$.user.profile.email"), None);
+        assert_eq!(get_selector(&rec, "$.events[5].type"), None);
+    }
+
+    #[test]
+    fn leaf_name_nombre_plano_queda_igual() {
+        assert_eq!(leaf_name("token"), "token");
+    }
+
+    #[test]
+    fn leaf_name_jsonpath_usa_ultimo_segmento() {
+        assert_eq!(leaf_name("$.user.profile.email"), "email");
+        assert_eq!(leaf_name("$.events[0].type"), "type");
+    }
+}
@@ -3,12 +3,15 @@ pub mod dag;
 pub mod task;
 pub mod worker;
 pub mod results;
-pub mod wordcount;
 pub mod engine; // ya lo teníamos
+pub mod schedule;
+pub mod jsonpath;
+pub mod task_log;
 
 pub use job::{JobId, JobInfo, JobRequest, JobStatus};
 pub use dag::{Dag, DagNode};
 pub use task::{Task, TaskId};
+pub use schedule::{ScheduleId, ScheduleInfo, ScheduleRequest};
 pub use worker::{
     WorkerId,
     WorkerRegisterRequest,
@@ -19,6 +22,12 @@ pub use worker::{
     TaskAssignmentResponse,
     TaskCompleteRequest,
     TaskCompleteResponse,
+    TaskStartedRequest,
+    TaskStartedResponse,
+    TaskProgressRequest,
+    TaskProgressResponse,
+    WorkerActivityState,
+    WorkerMetrics,
 };
 pub use results::JobResults;
 
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+use crate::dag::Dag;
+
+pub type ScheduleId = String;
+
+/// Alta de un job recurrente. Es mutuamente excluyente entre `interval_secs`
+/// (corre cada N segundos desde que se crea) y `cron` (expresión de 5
+/// campos "min hour dom month dow", en UTC). Debe venir exactamente uno
+/// de los dos.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRequest {
+    pub name: String,
+
+    /// DAG a correr en cada disparo, igual que en `JobRequest`.
+    pub dag: Dag,
+
+    pub parallelism: u32,
+    pub input_glob: String,
+    pub output_dir: String,
+
+    pub interval_secs: Option<u64>,
+    pub cron: Option<String>,
+}
+
+/// Vista pública de un `ScheduleEntry` del master, para `GET /api/v1/schedules`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleInfo {
+    pub id: ScheduleId,
+    pub name: String,
+    pub parallelism: u32,
+    pub input_glob: String,
+    pub output_dir: String,
+
+    /// Descripción legible del spec, ej: "every 300s" o "cron: */5 * * * *".
+    pub spec: String,
+
+    /// Epoch seconds de la próxima corrida.
+    pub next_run_secs: i64,
+
+    /// Job de la última corrida disparada por este schedule, si hubo alguna.
+    pub last_job: Option<String>,
+}
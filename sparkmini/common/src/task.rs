@@ -9,14 +9,18 @@ pub struct Task {
     pub id: TaskId,
     pub job_id: JobId,
 
-    /// Nodo lógico del DAG asociado a esta tarea (por ahora "wordcount" fijo)
+    /// Nodos lógicos del DAG que le tocan a esta tarea dentro de su etapa,
+    /// como un único string `"id1>id2>id3"` (ver `StageNode::node_ids` y
+    /// `PipelineState::new`, que lo separa para armar el pipeline).
     pub node_id: String,
 
     /// Número de intentos (para reintentos)
     pub attempt: u32,
 
-    /// Etapa lógica del job (0, 1, 2, ...)
-    /// Por ahora lo dejamos siempre en 0, pero queda listo para multi-stage.
+    /// Etapa lógica del job (0, 1, 2, ...): las etapas quedan separadas por
+    /// los operadores "anchos" del DAG (reduce_by_key, join,
+    /// aggregate_by_key), que son los
+    /// que necesitan un shuffle entre ellas (ver `schedule::plan_stages`).
     pub stage: u32,
 
     /// Partición lógica dentro de la etapa (0..parallelism-1)
@@ -30,4 +34,10 @@ pub struct Task {
 
     /// Ruta del archivo de salida para esta tarea
     pub output_path: String,
+
+    /// true si esta tarea es una copia especulativa lanzada porque la
+    /// original venía corriendo demasiado lento (straggler). Tiene un
+    /// `id` propio; la primera de las dos copias en completarse "gana".
+    #[serde(default)]
+    pub speculative: bool,
 }
@@ -0,0 +1,91 @@
+// common/src/task_log.rs
+//
+// Convención compartida entre worker y master para los logs por-intento de
+// una tarea (ver Proxmox `worker_task`): cada *intento* de una tarea recibe
+// un identificador propio y estable, `{job_id}:{stage}:{partition}:{attempt}:{start_unix}`,
+// y el worker le va apendeando líneas con timestamp a medida que progresa
+// (arranque, avance, spill, fin). El archivo vive bajo `task_log_dir()`, un
+// directorio que master y worker comparten igual que ya comparten
+// `input_path`/`output_path`, así el master puede sólo leerlo para servir
+// `GET /api/v1/tasks/{id}/log` sin tener que pedirle nada al worker.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_TASK_LOG_DIR: &str = "task_logs";
+
+/// Directorio donde viven los logs de tareas, compartido entre master y
+/// worker. Configurable con `TASK_LOG_DIR` (mismo patrón que `DATABASE_PATH`
+/// para el WAL del master).
+pub fn task_log_dir() -> String {
+    std::env::var("TASK_LOG_DIR").unwrap_or_else(|_| DEFAULT_TASK_LOG_DIR.to_string())
+}
+
+/// Identificador único de un *intento* de tarea: a diferencia de `Task::id`
+/// (estable a través de reintentos), éste cambia en cada intento porque
+/// incluye `attempt` y el instante en que arrancó, así dos corridas del
+/// mismo `Task::id` quedan en archivos de log separados.
+pub fn format_task_log_id(job_id: &str, stage: u32, partition: u32, attempt: u32, start_unix: u64) -> String {
+    format!("{job_id}:{stage}:{partition}:{attempt}:{start_unix}")
+}
+
+/// Segundos desde epoch, para usar como componente de `format_task_log_id`.
+pub fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn sanitize_for_filename(log_id: &str) -> String {
+    log_id.chars().map(|c| if c == ':' || c == '/' { '_' } else { c }).collect()
+}
+
+fn log_path_in(dir: &str, log_id: &str) -> PathBuf {
+    Path::new(dir).join(format!("{}.log", sanitize_for_filename(log_id)))
+}
+
+/// Ruta del archivo de log para `log_id` bajo `task_log_dir()`.
+pub fn log_path(log_id: &str) -> PathBuf {
+    log_path_in(&task_log_dir(), log_id)
+}
+
+/// Apendea una línea con timestamp (RFC3339 UTC) al log de `log_id`,
+/// creando el directorio y el archivo si hace falta. Pensado para el
+/// worker: arranque, avance, spill, fin de una tarea.
+pub fn append_line(log_id: &str, line: &str) -> io::Result<()> {
+    let dir = task_log_dir();
+    fs::create_dir_all(&dir)?;
+
+    let path = log_path_in(&dir, log_id);
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    let ts = chrono::Utc::now().to_rfc3339();
+    writeln!(file, "[{ts}] {line}")
+}
+
+/// Lee el contenido del log de `log_id` a partir del byte `from_offset`
+/// (0 para leerlo entero), y devuelve `(contenido_nuevo, offset_nuevo)` para
+/// que quien esté siguiéndolo (`?follow=true`) pueda pedir sólo lo que
+/// falta en la próxima llamada.
+pub fn read_from(log_id: &str, from_offset: u64) -> io::Result<(String, u64)> {
+    let path = log_path(log_id);
+
+    let mut file = match File::open(&path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((String::new(), from_offset)),
+        Err(e) => return Err(e),
+    };
+
+    let len = file.metadata()?.len();
+    if from_offset >= len {
+        return Ok((String::new(), len));
+    }
+
+    file.seek(SeekFrom::Start(from_offset))?;
+    let mut buf = String::new();
+    file.read_to_string(&mut buf)?;
+    Ok((buf, len))
+}
@@ -8,6 +8,13 @@ pub type WorkerId = String;
 pub struct WorkerRegisterRequest {
     pub hostname: String,
     pub max_concurrency: u32,
+
+    /// Prefijos de `input_path` que este worker tiene en disco local (ej:
+    /// "/data/input/north/"). El coordinador los usa para preferir
+    /// asignarle tareas cuyo input ya tiene local antes que mandárselas a
+    /// otro worker que tendría que traerlo de otro lado.
+    #[serde(default)]
+    pub local_paths: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,11 +25,28 @@ pub struct WorkerRegisterResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerHeartbeatRequest {
     pub worker_id: WorkerId,
+
+    /// Uso de CPU reportado por el worker (0-100).
+    pub cpu_percent: f32,
+    /// Memoria en uso del worker, en bytes.
+    pub mem_bytes: u64,
+    /// Cuántas tareas tiene el worker en su propia cola de ejecución en
+    /// este momento (además de las que ya están en in_flight del master).
+    #[serde(default)]
+    pub queue_depth: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerHeartbeatResponse {
     pub ok: bool,
+
+    /// Jobs cancelados de los que este worker tiene (o tenía) tareas en
+    /// vuelo: el worker los usa para abortar cualquier `ActiveTask` cuyo
+    /// `job_id` aparezca acá y limpiar su `output_path` parcial, en vez de
+    /// seguir gastando quantums en una tarea cuyo resultado ya nadie va a
+    /// leer (ver `cancel_job` en el master).
+    #[serde(default)]
+    pub cancelled_jobs: Vec<crate::JobId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +63,13 @@ pub struct TaskAssignmentResponse {
 pub struct TaskCompleteRequest {
     pub task_id: TaskId,
     pub success: bool,
+
+    /// Clasificación del error cuando `success == false`, ej:
+    /// "malformed_input", "bad_task". Si viene seteado, el coordinador
+    /// lo trata como no-reintentable y falla el job de inmediato, sin
+    /// importar cuántos intentos le queden a la tarea.
+    #[serde(default)]
+    pub error_kind: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,11 +77,64 @@ pub struct TaskCompleteResponse {
     pub ok: bool,
 }
 
+/// El worker avisa al master, apenas arranca a ejecutar una tarea, con qué
+/// identificador de log (ver `crate::task_log::format_task_log_id`) la va a
+/// ir registrando, así el master puede servir `GET /api/v1/tasks/{id}/log`
+/// (y reconstruir el índice de tareas activas en `active_tasks.json` si
+/// el master se reinicia) sin tener que preguntarle nada al worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskStartedRequest {
+    pub task_id: TaskId,
+    pub log_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskStartedResponse {
+    pub ok: bool,
+}
+
+/// El worker reporta avance incremental de una tarea en vuelo (cada
+/// quantum de `ActiveTask::step`, ver `worker::run`), así el master puede
+/// derivar `JobInfo::progress` sin esperar a que la tarea termine. Como el
+/// tamaño del input se conoce de antemano (`fs::metadata`), `total_bytes`
+/// viene fijo desde el primer reporte y sólo cambia `processed_bytes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskProgressRequest {
+    pub task_id: TaskId,
+    pub processed_bytes: u64,
+    pub total_bytes: u64,
+    pub processed_records: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskProgressResponse {
+    pub ok: bool,
+}
+
+/// Clasificación de alto nivel del estado de un worker, pensada para
+/// `client workers` (no confundir con `master::background::WorkerState`,
+/// que clasifica a los *background workers* del master, no a los workers
+/// del cluster).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerActivityState {
+    /// Tiene al menos una tarea in-flight en este momento.
+    Active,
+    /// Registrado y con heartbeat al día, pero sin tareas asignadas.
+    Idle,
+    /// Dejó de mandar heartbeats dentro de `WORKER_HEARTBEAT_TIMEOUT_SECS`.
+    Dead,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct WorkerMetrics {
     pub worker_id: WorkerId,
     pub hostname: String,
     pub dead: bool,
+    /// Clasificación explícita del estado del worker (ver
+    /// `WorkerActivityState`), derivada de `dead` y de si tiene alguna
+    /// tarea in-flight en este momento.
+    pub state: WorkerActivityState,
     pub max_concurrency: u32,
     pub last_heartbeat_secs_ago: u64,
     pub active_tasks: u32,
@@ -58,5 +142,11 @@ pub struct WorkerMetrics {
     pub tasks_succeeded: u64,
     pub tasks_failed: u64,
     pub avg_task_ms: Option<f64>,
+
+    /// Última carga reportada por el worker en su heartbeat. `None` si
+    /// todavía no mandó ninguno.
+    pub cpu_percent: Option<f32>,
+    pub mem_bytes: Option<u64>,
+    pub queue_depth: Option<u32>,
 }
 
@@ -0,0 +1,184 @@
+// master/src/background.rs
+//
+// Las tareas de mantenimiento del master (detectar workers muertos, drenar
+// la cola de reintentos, detectar stragglers) se modelan como
+// implementaciones de `BackgroundWorker`, registradas en un
+// `BackgroundManager` dentro de `AppState`. Cada una corre en su propia
+// tarea de tokio, con un canal de control para pausarla/reanudarla/
+// cancelarla, y su estado queda visible vía `GET /api/v1/background`.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::state::AppState;
+
+/// Estado de un worker en segundo plano, reportado luego de cada `step`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerState {
+    Active,
+    Idle,
+    Dead,
+}
+
+/// Órdenes que se le pueden mandar a un worker en ejecución por su canal
+/// de control.
+#[derive(Debug, Clone, Copy)]
+pub enum Control {
+    Pause,
+    Resume,
+    Cancel,
+}
+
+/// Una tarea de mantenimiento con loop propio dentro del master. `step`
+/// hace una pasada y devuelve el estado resultante; si devuelve
+/// `WorkerState::Dead` el manager deja de llamarlo.
+pub trait BackgroundWorker: Send {
+    fn name(&self) -> &str;
+
+    fn step<'a>(
+        &'a mut self,
+        state: &'a AppState,
+    ) -> Pin<Box<dyn Future<Output = WorkerState> + Send + 'a>>;
+
+    /// Hook de reporte de errores; por defecto solo loggea con `warn!`.
+    /// Las implementaciones que quieran exponer el último error vía
+    /// `last_error` deben guardarlo acá también.
+    fn on_error(&mut self, err: &str) {
+        warn!("{}: error en background worker: {}", self.name(), err);
+    }
+
+    /// Último error reportado por este worker, si lo hay. Por defecto
+    /// ninguno.
+    fn last_error(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Snapshot público del estado de un worker, para `GET /api/v1/background`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundWorkerInfo {
+    pub name: String,
+    pub state: WorkerState,
+    pub last_tick_secs_ago: u64,
+    pub last_error: Option<String>,
+}
+
+struct WorkerHandle {
+    state: Arc<Mutex<WorkerState>>,
+    last_tick: Arc<Mutex<SystemTime>>,
+    last_error: Arc<Mutex<Option<String>>>,
+    control: mpsc::Sender<Control>,
+}
+
+/// Registro de los workers en segundo plano del master. Cada worker
+/// registrado corre en su propia tarea de tokio, con su propio intervalo
+/// de tick y canal de control.
+#[derive(Clone, Default)]
+pub struct BackgroundManager {
+    workers: Arc<Mutex<HashMap<String, WorkerHandle>>>,
+}
+
+impl BackgroundManager {
+    /// Registra un worker y lo arranca en su propia tarea de tokio,
+    /// llamando a `step` cada `tick_interval`.
+    pub fn spawn(&self, mut worker: Box<dyn BackgroundWorker>, app_state: AppState, tick_interval: Duration) {
+        let name = worker.name().to_string();
+        let state_handle = Arc::new(Mutex::new(WorkerState::Idle));
+        let last_tick = Arc::new(Mutex::new(SystemTime::now()));
+        let last_error = Arc::new(Mutex::new(None));
+        let (tx, mut rx) = mpsc::channel::<Control>(8);
+
+        self.workers.lock().unwrap().insert(
+            name.clone(),
+            WorkerHandle {
+                state: state_handle.clone(),
+                last_tick: last_tick.clone(),
+                last_error: last_error.clone(),
+                control: tx,
+            },
+        );
+
+        tokio::spawn(async move {
+            let mut paused = false;
+
+            loop {
+                tokio::select! {
+                    _ = sleep(tick_interval) => {
+                        if paused {
+                            continue;
+                        }
+
+                        let result = worker.step(&app_state).await;
+                        *last_tick.lock().unwrap() = SystemTime::now();
+                        *last_error.lock().unwrap() = worker.last_error();
+                        *state_handle.lock().unwrap() = result;
+
+                        if result == WorkerState::Dead {
+                            info!("{}: worker en segundo plano terminó", worker.name());
+                            break;
+                        }
+                    }
+                    cmd = rx.recv() => {
+                        match cmd {
+                            Some(Control::Pause) => {
+                                paused = true;
+                                info!("{}: pausado", worker.name());
+                            }
+                            Some(Control::Resume) => {
+                                paused = false;
+                                info!("{}: reanudado", worker.name());
+                            }
+                            Some(Control::Cancel) | None => {
+                                *state_handle.lock().unwrap() = WorkerState::Dead;
+                                info!("{}: cancelado", worker.name());
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Envía una orden de control al worker con ese nombre. Devuelve
+    /// `false` si no existe ningún worker registrado con ese nombre.
+    pub fn send_control(&self, worker_name: &str, control: Control) -> bool {
+        let workers = self.workers.lock().unwrap();
+        match workers.get(worker_name) {
+            Some(handle) => handle.control.try_send(control).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Snapshot del estado de todos los workers registrados, para
+    /// `GET /api/v1/background`.
+    pub fn snapshot(&self) -> Vec<BackgroundWorkerInfo> {
+        let now = SystemTime::now();
+        let workers = self.workers.lock().unwrap();
+
+        let mut out: Vec<BackgroundWorkerInfo> = workers
+            .iter()
+            .map(|(name, handle)| {
+                let last_tick_at = *handle.last_tick.lock().unwrap();
+                BackgroundWorkerInfo {
+                    name: name.clone(),
+                    state: *handle.state.lock().unwrap(),
+                    last_tick_secs_ago: now.duration_since(last_tick_at).unwrap_or_default().as_secs(),
+                    last_error: handle.last_error.lock().unwrap().clone(),
+                }
+            })
+            .collect();
+
+        out.sort_by(|a, b| a.name.cmp(&b.name));
+        out
+    }
+}
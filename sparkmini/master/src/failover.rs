@@ -1,31 +1,147 @@
 use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
 use std::time::{Duration, SystemTime};
 
-use tokio::time::sleep;
 use tracing::{info, warn};
 
 use common::JobStatus;
 
+use crate::background::{BackgroundWorker, WorkerState};
 use crate::state::{AppState, InFlight};
-use crate::{FAILOVER_SWEEP_INTERVAL_SECS, MAX_TASK_ATTEMPTS, WORKER_HEARTBEAT_TIMEOUT_SECS};
+use crate::wal::WalEvent;
+use crate::{MAX_TASK_ATTEMPTS, WORKER_HEARTBEAT_TIMEOUT_SECS};
 
-/// Loop principal de tolerancia a fallos:
-/// - detecta workers muertos (sin heartbeat)
-/// - reencola tareas
-pub async fn run_failover_loop(state: AppState) {
-    loop {
-        sleep(Duration::from_secs(FAILOVER_SWEEP_INTERVAL_SECS)).await;
+/// Umbral piso para considerar una tarea "straggler", independiente del
+/// baseline: evitamos especular sobre tareas que recién arrancaron.
+const STRAGGLER_FLOOR_MS: u64 = 2_000;
+const STRAGGLER_MULTIPLIER: f64 = 1.5;
 
-        if let Err(e) = sweep_once(&state) {
-            warn!("error en failover sweep: {:?}", e);
+/// Percentil (0.0..=1.0) de duraciones ya completadas, usado como baseline
+/// en vez del promedio: el promedio se deja arrastrar por una sola tarea
+/// rápida o lenta, mientras que la mediana/p75 reflejan mejor "cuánto
+/// tarda normalmente" una tarea de esta etapa.
+fn percentile_ms(durations: &[u64], pct: f64) -> u64 {
+    let mut sorted = durations.to_vec();
+    sorted.sort_unstable();
+    let idx = ((sorted.len() as f64 - 1.0) * pct).round() as usize;
+    sorted[idx.min(sorted.len() - 1)]
+}
+
+/// Busca tareas en vuelo que estén tardando mucho más que la mediana de
+/// lo que tardaron las demás tareas de su misma etapa, y lanza una copia
+/// especulativa de cada una (como mucho una por tarea, y nunca para una
+/// que ya agotó `MAX_TASK_ATTEMPTS`) para que otro worker la corra en
+/// paralelo; la primera copia en terminar gana.
+/// Devuelve cuántas copias especulativas se lanzaron en esta pasada.
+pub(crate) fn detect_stragglers(state: &AppState) -> usize {
+    let now = SystemTime::now();
+
+    let baselines: std::collections::HashMap<(String, u32), u64> = {
+        let durations = state.stage_durations_ms.lock().unwrap();
+        durations
+            .iter()
+            .filter(|(_, v)| !v.is_empty())
+            .map(|(k, v)| (k.clone(), percentile_ms(v, 0.5)))
+            .collect()
+    };
+
+    let mut duplicates: Vec<common::Task> = Vec::new();
+
+    {
+        let in_flight = state.in_flight.lock().unwrap();
+        let mut speculated = state.speculated.lock().unwrap();
+
+        for inf in in_flight.values() {
+            let task = &inf.task;
+            let key = (task.job_id.clone(), task.stage, task.partition);
+
+            if speculated.contains(&key) || task.attempt >= MAX_TASK_ATTEMPTS {
+                continue;
+            }
+
+            let elapsed_ms = now
+                .duration_since(inf.dispatched_at)
+                .unwrap_or_default()
+                .as_millis() as u64;
+
+            let baseline_ms = baselines
+                .get(&(task.job_id.clone(), task.stage))
+                .copied()
+                .unwrap_or(STRAGGLER_FLOOR_MS);
+
+            let threshold_ms =
+                ((baseline_ms as f64) * STRAGGLER_MULTIPLIER).max(STRAGGLER_FLOOR_MS as f64) as u64;
+
+            if elapsed_ms > threshold_ms {
+                warn!(
+                    "tarea {} (job={}, stage={}, partition={}) tardó {}ms (baseline {}ms), lanzando copia especulativa",
+                    task.id, task.job_id, task.stage, task.partition, elapsed_ms, baseline_ms
+                );
+
+                let mut dup = task.clone();
+                dup.id = uuid::Uuid::new_v4().to_string();
+                dup.speculative = true;
+                duplicates.push(dup);
+                speculated.insert(key);
+            }
+        }
+    }
+
+    let n = duplicates.len();
+
+    if !duplicates.is_empty() {
+        {
+            let mut queue = state.tasks_queue.lock().unwrap();
+            for dup in duplicates {
+                queue.push_back(dup);
+            }
+        }
+        crate::handlers::push_pending_tasks(state);
+    }
+
+    n
+}
+
+/// Saca de `retry_queue` (un min-heap por `ready_at`) todas las tareas
+/// que ya están listas para reintentarse y las pasa a `tasks_queue`.
+/// Devuelve cuántas tareas se reencolaron en esta pasada.
+fn drain_ready_retries(state: &AppState) -> usize {
+    let now = SystemTime::now();
+    let mut ready = Vec::new();
+
+    {
+        let mut retry_queue = state.retry_queue.lock().unwrap();
+        while let Some(std::cmp::Reverse(delayed)) = retry_queue.peek().cloned() {
+            if delayed.ready_at > now {
+                break;
+            }
+            retry_queue.pop();
+            ready.push(delayed.task);
+        }
+    }
+
+    let n = ready.len();
+
+    if !ready.is_empty() {
+        state.wal.append(WalEvent::TasksEnqueued { tasks: ready.clone() });
+        {
+            let mut queue = state.tasks_queue.lock().unwrap();
+            for task in ready {
+                info!("tarea {} sale de la cola de reintentos, lista para reencolar", task.id);
+                queue.push_back(task);
+            }
         }
+        crate::handlers::push_pending_tasks(state);
     }
+
+    n
 }
 
 /// Una pasada de chequeo:
 /// 1. marca workers muertos
 /// 2. saca tareas de in_flight de esos workers
-/// 3. reencola las tareas (si no superan MAX_TASK_ATTEMPTS)
+/// 3. las manda a `retry_queue` con backoff (si no superan MAX_TASK_ATTEMPTS)
 fn sweep_once(state: &AppState) -> Result<(), String> {
     let now = SystemTime::now();
 
@@ -45,6 +161,7 @@ fn sweep_once(state: &AppState) -> Result<(), String> {
                     if elapsed > Duration::from_secs(WORKER_HEARTBEAT_TIMEOUT_SECS) {
                         meta.dead = true;
                         newly_dead_workers.push(worker_id.clone());
+                        state.wal.append(WalEvent::WorkerDead { worker_id: worker_id.clone() });
                         warn!(
                             "marcando worker {} como DEAD (sin heartbeat hace {:?})",
                             worker_id, elapsed
@@ -67,10 +184,17 @@ fn sweep_once(state: &AppState) -> Result<(), String> {
     let dead_set: HashSet<String> = newly_dead_workers.iter().cloned().collect();
 
     // 2) Sacar tareas de in_flight que pertenecían a esos workers
-    use common::Task;
-    let mut to_requeue: Vec<Task> = Vec::new();
+    let mut to_retry: Vec<crate::retry::DelayedTask> = Vec::new();
     let mut jobs_to_fail: Vec<String> = Vec::new();
 
+    let cancelled_job_ids: HashSet<String> = {
+        let jobs = state.jobs.lock().map_err(|_| "lock jobs")?;
+        jobs.values()
+            .filter(|j| j.status == JobStatus::Cancelled)
+            .map(|j| j.id.clone())
+            .collect()
+    };
+
     {
         let mut in_flight = state.in_flight.lock().map_err(|_| "lock in_flight")?;
 
@@ -79,15 +203,37 @@ fn sweep_once(state: &AppState) -> Result<(), String> {
 
         for (_task_id, inflight) in in_flight.drain() {
             if dead_set.contains(&inflight.worker_id) {
-                let mut task = inflight.task.clone();
+                let task = inflight.task.clone();
 
+                if cancelled_job_ids.contains(&task.job_id) {
+                    // El job ya fue cancelado: no tiene sentido reencolar
+                    // ni contarla como intento fallido.
+                    info!(
+                        "tarea {} del job {} (cancelado) se descarta tras caída de worker {}",
+                        task.id, task.job_id, inflight.worker_id
+                    );
+                    continue;
+                }
+
+                let mut task = task;
                 if task.attempt + 1 <= MAX_TASK_ATTEMPTS {
+                    // Igual que en `complete_task`: no la reencolamos de
+                    // una en `tasks_queue`, sino que cae a `retry_queue`
+                    // con el mismo backoff exponencial. Si no, una tarea
+                    // cuyo worker muere una y otra vez (ej: un worker que
+                    // crashea apenas arranca a ejecutarla) hot-loopea sus
+                    // reintentos exactamente como el bug original.
+                    let ready_at = SystemTime::now() + crate::retry::backoff_delay(task.attempt);
                     task.attempt += 1;
                     info!(
-                        "reencolando tarea {} del job {} por caída del worker {} (attempt={})",
-                        task.id, task.job_id, inflight.worker_id, task.attempt
+                        "tarea {} del job {} cae a la cola de reintentos por caída del worker {} (attempt={}), retry en {:?}",
+                        task.id,
+                        task.job_id,
+                        inflight.worker_id,
+                        task.attempt,
+                        ready_at.duration_since(SystemTime::now()).unwrap_or_default()
                     );
-                    to_requeue.push(task);
+                    to_retry.push(crate::retry::DelayedTask { ready_at, task });
                 } else {
                     warn!(
                         "tarea {} del job {} superó el máximo de intentos ({}) tras caída de worker {}, marcando job como FAILED",
@@ -104,11 +250,13 @@ fn sweep_once(state: &AppState) -> Result<(), String> {
         *in_flight = new_in_flight;
     }
 
-    // 3) Reencolar las tareas que sí se pueden reintentar
-    if !to_requeue.is_empty() {
-        let mut queue = state.tasks_queue.lock().map_err(|_| "lock tasks_queue")?;
-        for t in to_requeue {
-            queue.push_back(t);
+    // 3) Mandar a la cola de reintentos las tareas que sí se pueden
+    // reintentar; `drain_ready_retries` las va a pasar a `tasks_queue`
+    // (con su propio WalEvent::TasksEnqueued) cuando venza su backoff.
+    if !to_retry.is_empty() {
+        let mut retry_queue = state.retry_queue.lock().map_err(|_| "lock retry_queue")?;
+        for delayed in to_retry {
+            retry_queue.push(std::cmp::Reverse(delayed));
         }
     }
 
@@ -118,9 +266,94 @@ fn sweep_once(state: &AppState) -> Result<(), String> {
         for job_id in jobs_to_fail {
             if let Some(job) = jobs.get_mut(&job_id) {
                 job.status = JobStatus::Failed;
+                state.wal.append(WalEvent::JobUpdated { job: job.clone() });
             }
         }
     }
 
     Ok(())
 }
+
+/* ---------------- BackgroundWorker: registrados en AppState.background ---------------- */
+
+/// Detecta workers muertos (sin heartbeat) y reencola sus tareas en vuelo.
+/// Reimplementación del viejo loop hardcodeado como `BackgroundWorker`.
+pub struct DeadWorkerMonitor {
+    last_error: Option<String>,
+}
+
+impl DeadWorkerMonitor {
+    pub fn new() -> Self {
+        Self { last_error: None }
+    }
+}
+
+impl BackgroundWorker for DeadWorkerMonitor {
+    fn name(&self) -> &str {
+        "dead_worker_monitor"
+    }
+
+    fn step<'a>(
+        &'a mut self,
+        state: &'a AppState,
+    ) -> Pin<Box<dyn Future<Output = WorkerState> + Send + 'a>> {
+        Box::pin(async move {
+            match sweep_once(state) {
+                Ok(()) => self.last_error = None,
+                Err(e) => {
+                    self.on_error(&e);
+                    self.last_error = Some(e);
+                }
+            }
+            WorkerState::Active
+        })
+    }
+
+    fn last_error(&self) -> Option<String> {
+        self.last_error.clone()
+    }
+}
+
+/// Drena la cola de reintentos con backoff hacia `tasks_queue`.
+pub struct RetryDrainWorker;
+
+impl BackgroundWorker for RetryDrainWorker {
+    fn name(&self) -> &str {
+        "retry_drain"
+    }
+
+    fn step<'a>(
+        &'a mut self,
+        state: &'a AppState,
+    ) -> Pin<Box<dyn Future<Output = WorkerState> + Send + 'a>> {
+        Box::pin(async move {
+            let n = drain_ready_retries(state);
+            if n > 0 {
+                info!("retry_drain: reencoló {} tarea(s) desde la cola de reintentos", n);
+            }
+            WorkerState::Active
+        })
+    }
+}
+
+/// Detecta tareas stragglers en vuelo y lanza copias especulativas.
+pub struct StragglerDetector;
+
+impl BackgroundWorker for StragglerDetector {
+    fn name(&self) -> &str {
+        "straggler_detector"
+    }
+
+    fn step<'a>(
+        &'a mut self,
+        state: &'a AppState,
+    ) -> Pin<Box<dyn Future<Output = WorkerState> + Send + 'a>> {
+        Box::pin(async move {
+            let n = detect_stragglers(state);
+            if n > 0 {
+                info!("straggler_detector: lanzó {} copia(s) especulativa(s)", n);
+            }
+            WorkerState::Active
+        })
+    }
+}
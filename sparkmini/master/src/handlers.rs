@@ -1,36 +1,66 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    routing::{get, post},
+    response::sse::{Event, KeepAlive, Sse},
+    routing::{delete, get, post},
     Json, Router,
 };
 use common::{
     JobInfo, JobRequest, JobResults, JobStatus, TaskAssignmentRequest,
     TaskAssignmentResponse, TaskCompleteRequest, TaskCompleteResponse,
+    TaskProgressRequest, TaskProgressResponse,
+    TaskStartedRequest, TaskStartedResponse,
     WorkerHeartbeatRequest, WorkerHeartbeatResponse, WorkerRegisterRequest,
-    WorkerRegisterResponse, WorkerMetrics, WorkerId,
+    WorkerRegisterResponse, WorkerMetrics, WorkerActivityState, WorkerId,
 };
+use futures_core::Stream;
 use glob::glob;
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::convert::Infallible;
 use std::fs;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
 use tracing::info;
 use chrono::Utc;
 use std::time::SystemTime;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
-use crate::state::{AppState, InFlight, WorkerMeta};
-use crate::MAX_TASK_ATTEMPTS;
+use crate::background::{BackgroundWorkerInfo, Control};
+use crate::schedule::{plan_stages, StageNode};
+use crate::state::{AppState, InFlight, TaskLogMeta, TaskProgress, WorkerMeta};
+use crate::wal::WalEvent;
+use crate::{MAX_TASK_ATTEMPTS, MAX_WORKER_CPU_PERCENT};
 
 pub fn build_router(state: AppState) -> Router {
     Router::new()
         .route("/health", get(health))
-        .route("/api/v1/jobs", post(create_job))
+        .route("/api/v1/jobs", get(list_jobs).post(create_job))
         .route("/api/v1/jobs/:id", get(get_job))
         .route("/api/v1/jobs/:id/results", get(get_job_results))
+        .route("/api/v1/jobs/:id/cancel", post(cancel_job))
+        .route("/api/v1/jobs/:id/pause", post(pause_job))
+        .route("/api/v1/jobs/:id/resume", post(resume_job))
         .route("/api/v1/workers", get(list_workers))
         .route("/api/v1/workers/register", post(register_worker))
         .route("/api/v1/workers/heartbeat", post(worker_heartbeat))
+        .route("/api/v1/workers/:id/stream", get(worker_stream))
+        .route("/api/v1/background", get(list_background_workers))
+        .route("/api/v1/background/:name/pause", post(pause_background_worker))
+        .route("/api/v1/background/:name/resume", post(resume_background_worker))
+        .route("/api/v1/background/:name/cancel", post(cancel_background_worker))
         .route("/api/v1/tasks/next", post(assign_task))
         .route("/api/v1/tasks/complete", post(complete_task))
+        .route("/api/v1/tasks/started", post(task_started))
+        .route("/api/v1/tasks/:id/progress", post(task_progress))
+        .route("/api/v1/tasks/:id/log", get(get_task_log))
+        .route(
+            "/api/v1/schedules",
+            get(crate::scheduler::list_schedules).post(crate::scheduler::create_schedule),
+        )
+        .route("/api/v1/schedules/:id", delete(crate::scheduler::delete_schedule))
         .with_state(state)
 }
 
@@ -40,20 +70,34 @@ async fn health() -> &'static str {
     "ok"
 }
 
-// Crea un job nuevo y genera una tarea por archivo que haga match con input_glob
+// Crea un job nuevo: parsea el DAG en etapas (separadas por operadores
+// anchos como reduce_by_key/join) y sólo encola las tareas de la etapa 0;
+// el resto de las etapas quedan "pending" hasta que complete_task las
+// vaya materializando a medida que se van completando.
 async fn create_job(
     State(state): State<AppState>,
     Json(req): Json<JobRequest>,
 ) -> Json<JobInfo> {
+    Json(submit_job(&state, req))
+}
+
+/// Lógica real de alta de un job: parte el DAG en etapas, encola las
+/// tareas de la etapa 0 y deja el resto como pendiente. La usan tanto
+/// el handler HTTP `create_job` como el scheduler de jobs recurrentes
+/// (`crate::scheduler`) para materializar cada corrida programada.
+pub(crate) fn submit_job(state: &AppState, req: JobRequest) -> JobInfo {
     use common::Task;
 
     let job_id = uuid::Uuid::new_v4().to_string();
     let job_output_dir = format!("/data/output/{}", job_id);
     let _ = fs::create_dir_all(&job_output_dir);
 
-    let mut tasks_for_job: Vec<Task> = Vec::new();
-
     let par = req.parallelism.max(1);
+    let mut stages = plan_stages(&req.dag, par);
+    let stage0 = stages.remove(0);
+    let node_id0 = stage0.node_ids.join(">");
+
+    let mut tasks_for_job: Vec<Task> = Vec::new();
     let mut next_partition: u32 = 0;
 
     for entry in glob(&req.input_glob).expect("patrón input_glob inválido") {
@@ -62,28 +106,29 @@ async fn create_job(
                 let input_path = path.to_string_lossy().to_string();
 
                 let file_name = path.file_name().unwrap().to_string_lossy();
-                let output_path = format!("{}/{}", job_output_dir, file_name);
+                let output_path = format!("{}/stage0/{}", job_output_dir, file_name);
 
-                let partition = next_partition % par;
+                let partition = next_partition % stage0.parallelism;
                 next_partition += 1;
 
                 let t = Task {
                     id: uuid::Uuid::new_v4().to_string(),
                     job_id: job_id.clone(),
-                    node_id: "wordcount".to_string(),
+                    node_id: node_id0.clone(),
                     attempt: 0,
                     stage: 0,
                     partition,
-                    parallelism: par,
+                    parallelism: stage0.parallelism,
                     input_path,
                     output_path,
+                    speculative: false,
                 };
                 tasks_for_job.push(t);
             }
         }
     }
 
-    let initial_status = if tasks_for_job.is_empty() {
+    let initial_status = if tasks_for_job.is_empty() && stages.is_empty() {
         JobStatus::Succeeded
     } else {
         JobStatus::Accepted
@@ -107,25 +152,183 @@ async fn create_job(
         total_tasks,
         completed_tasks: 0,
         failed_tasks: 0,
+        retries: 0,
+        progress: if initial_status == JobStatus::Succeeded { 1.0 } else { 0.0 },
     };
 
     {
         let mut jobs = state.jobs.lock().unwrap();
         jobs.insert(job_id.clone(), job_info.clone());
     }
+    state.wal.append(WalEvent::JobCreated { job: job_info.clone() });
+
+    if !stages.is_empty() {
+        let mut pending = state.pending_stages.lock().unwrap();
+        pending.insert(job_id.clone(), stages);
+    }
 
     if !tasks_for_job.is_empty() {
+        state.wal.append(WalEvent::TasksEnqueued { tasks: tasks_for_job.clone() });
         let mut queue = state.tasks_queue.lock().unwrap();
         for t in tasks_for_job {
             queue.push_back(t);
         }
     }
 
-    Json(job_info)
+    push_pending_tasks(state);
+
+    job_info
+}
+
+/// Reparte los outputs de la etapa que acaba de terminar entre las
+/// particiones de la próxima etapa usando hash-shuffle: la partición de
+/// destino `p` recibe los outputs de toda partición de origen cuyo
+/// `partition % next_parallelism == p`.
+fn shuffle_next_stage_inputs(
+    upstream_outputs: &[(u32, String)],
+    next_parallelism: u32,
+) -> Vec<Vec<String>> {
+    let mut inputs: Vec<Vec<String>> = vec![Vec::new(); next_parallelism as usize];
+    for (src_partition, output_path) in upstream_outputs {
+        let dst = (src_partition % next_parallelism) as usize;
+        inputs[dst].push(output_path.clone());
+    }
+    inputs
+}
+
+/// Arma y encola las tareas de la próxima etapa pendiente de un job, a
+/// partir de los outputs que dejó la etapa que recién terminó. Devuelve
+/// cuántas tareas nuevas se encolaron (0 si ya no quedaban etapas).
+fn materialize_next_stage(
+    state: &AppState,
+    job_id: &str,
+    finished_stage: u32,
+    job_output_dir: &str,
+) -> u32 {
+    let next_stage_plan: Option<StageNode> = {
+        let mut pending = state.pending_stages.lock().unwrap();
+        if let Some(stages) = pending.get_mut(job_id) {
+            if !stages.is_empty() {
+                Some(stages.remove(0))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    };
+
+    let Some(plan) = next_stage_plan else {
+        return 0;
+    };
+
+    let upstream_outputs: Vec<(u32, String)> = {
+        let mut outputs = state.stage_outputs.lock().unwrap();
+        outputs
+            .remove(&(job_id.to_string(), finished_stage))
+            .unwrap_or_default()
+    };
+
+    let shuffled = shuffle_next_stage_inputs(&upstream_outputs, plan.parallelism);
+    let node_id = plan.node_ids.join(">");
+
+    let mut new_tasks: Vec<common::Task> = Vec::new();
+    for (partition, inputs) in shuffled.into_iter().enumerate() {
+        let partition = partition as u32;
+        let output_path = format!(
+            "{}/stage{}/part-{}.out",
+            job_output_dir, plan.stage, partition
+        );
+        new_tasks.push(common::Task {
+            id: uuid::Uuid::new_v4().to_string(),
+            job_id: job_id.to_string(),
+            node_id: node_id.clone(),
+            attempt: 0,
+            stage: plan.stage,
+            partition,
+            parallelism: plan.parallelism,
+            input_path: inputs.join(","),
+            output_path,
+            speculative: false,
+        });
+    }
+
+    let count = new_tasks.len() as u32;
+    if count > 0 {
+        state.wal.append(WalEvent::TasksEnqueued { tasks: new_tasks.clone() });
+        {
+            let mut queue = state.tasks_queue.lock().unwrap();
+            for t in new_tasks {
+                queue.push_back(t);
+            }
+        }
+        push_pending_tasks(state);
+    }
+    count
 }
 
+// Barrier de fin de etapa: si ya no quedan tareas de `stage` en la cola,
+// in_flight ni el retry queue, materializa la próxima etapa (o marca el
+// job Succeeded si no había más). Se invoca tanto cuando la tarea
+// ganadora de una partición completa como cuando llega tarde la copia
+// perdedora de una carrera especulativa: esa segunda llamada es la única
+// oportunidad de evaluar el barrier si, al completar la ganadora, la
+// perdedora seguía in_flight (ver comentario en `complete_task`).
+fn try_materialize_stage_barrier(state: &AppState, job_id: &str, stage: u32) {
+    let queue_has_for_stage = {
+        let queue = state.tasks_queue.lock().unwrap();
+        queue.iter().any(|t| t.job_id == job_id && t.stage == stage)
+    };
 
+    let inflight_has_for_stage = {
+        let inflight_map = state.in_flight.lock().unwrap();
+        inflight_map
+            .values()
+            .any(|it| it.task.job_id == job_id && it.task.stage == stage)
+    };
 
+    let retry_has_for_stage = {
+        let retry_queue = state.retry_queue.lock().unwrap();
+        retry_queue
+            .iter()
+            .any(|d| d.0.task.job_id == job_id && d.0.task.stage == stage)
+    };
+
+    if !queue_has_for_stage && !inflight_has_for_stage && !retry_has_for_stage {
+        let job_output_dir = state
+            .jobs
+            .lock()
+            .unwrap()
+            .get(job_id)
+            .map(|j| j.output_dir.clone())
+            .unwrap_or_default();
+        let new_tasks = materialize_next_stage(state, job_id, stage, &job_output_dir);
+
+        let mut jobs = state.jobs.lock().unwrap();
+        if let Some(job) = jobs.get_mut(job_id) {
+            if new_tasks > 0 {
+                job.total_tasks += new_tasks;
+            } else if !matches!(job.status, JobStatus::Failed) {
+                job.status = JobStatus::Succeeded;
+                job.finished_at = Some(Utc::now());
+            }
+        }
+    }
+
+    recompute_job_progress(state, job_id);
+
+    if let Some(job) = state.jobs.lock().unwrap().get(job_id) {
+        state.wal.append(WalEvent::JobUpdated { job: job.clone() });
+    }
+}
+
+// Lista todos los jobs conocidos (para el dashboard del cliente, entre
+// otros usos), sin paginar: el repo todavía no tiene tantos jobs vivos
+// como para que eso sea un problema.
+async fn list_jobs(State(state): State<AppState>) -> Json<Vec<JobInfo>> {
+    let jobs = state.jobs.lock().unwrap();
+    Json(jobs.values().cloned().collect())
+}
 
 // Devuelve info básica de un job
 async fn get_job(
@@ -177,6 +380,108 @@ async fn get_job_results(
     Ok(Json(results))
 }
 
+/// Cancela un job: lo marca `Cancelled`, descarta sus tareas pendientes
+/// (en `tasks_queue`, en `retry_queue` y en `pending_stages`) y deja de
+/// reintentarlo. Sus tareas ya en vuelo no se pueden sacar de `in_flight`
+/// desde acá, pero `worker_heartbeat` le avisa al worker que las tiene
+/// para que las aborte y limpie su output parcial; cuando esa tarea
+/// reporte (éxito o error), `complete_task` la descarta porque el job está
+/// `Cancelled`.
+async fn cancel_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<JobInfo>, StatusCode> {
+    let job = {
+        let mut jobs = state.jobs.lock().unwrap();
+        let Some(job) = jobs.get_mut(&id) else {
+            return Err(StatusCode::NOT_FOUND);
+        };
+
+        if matches!(job.status, JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled) {
+            return Ok(Json(job.clone()));
+        }
+
+        job.status = JobStatus::Cancelled;
+        job.finished_at = Some(Utc::now());
+        job.clone()
+    };
+
+    {
+        let mut queue = state.tasks_queue.lock().unwrap();
+        queue.retain(|t| t.job_id != id);
+    }
+
+    {
+        let mut retry_queue = state.retry_queue.lock().unwrap();
+        let kept: Vec<_> = retry_queue
+            .drain()
+            .filter(|Reverse(delayed)| delayed.task.job_id != id)
+            .collect();
+        *retry_queue = kept.into_iter().collect();
+    }
+
+    state.pending_stages.lock().unwrap().remove(&id);
+
+    state.wal.append(WalEvent::JobUpdated { job: job.clone() });
+
+    info!("job {} cancelado", id);
+    Ok(Json(job))
+}
+
+/// Pausa un job: el scheduler lo saltea al asignar tareas
+/// (`try_assign_task`) sin sacar sus tareas de `tasks_queue`, así que
+/// conservan su posición hasta que se reanude.
+async fn pause_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<JobInfo>, StatusCode> {
+    let job = {
+        let mut jobs = state.jobs.lock().unwrap();
+        let Some(job) = jobs.get_mut(&id) else {
+            return Err(StatusCode::NOT_FOUND);
+        };
+
+        if matches!(job.status, JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled) {
+            return Err(StatusCode::CONFLICT);
+        }
+
+        job.status = JobStatus::Paused;
+        job.clone()
+    };
+
+    state.wal.append(WalEvent::JobUpdated { job: job.clone() });
+    info!("job {} pausado", id);
+    Ok(Json(job))
+}
+
+/// Reanuda un job pausado: vuelve a `Running` (sus tareas ya estaban en
+/// `tasks_queue`, sólo hacía falta que `try_assign_task` dejara de
+/// saltearlas) y dispara un intento de push inmediato por si algún
+/// worker tiene el stream abierto esperando.
+async fn resume_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<JobInfo>, StatusCode> {
+    let job = {
+        let mut jobs = state.jobs.lock().unwrap();
+        let Some(job) = jobs.get_mut(&id) else {
+            return Err(StatusCode::NOT_FOUND);
+        };
+
+        if !matches!(job.status, JobStatus::Paused) {
+            return Err(StatusCode::CONFLICT);
+        }
+
+        job.status = JobStatus::Running;
+        job.clone()
+    };
+
+    state.wal.append(WalEvent::JobUpdated { job: job.clone() });
+    push_pending_tasks(&state);
+    info!("job {} reanudado", id);
+    Ok(Json(job))
+}
+
 // Registra un worker nuevo
 async fn register_worker(
     State(state): State<AppState>,
@@ -198,6 +503,11 @@ async fn register_worker(
                 tasks_succeeded: 0,
                 tasks_failed: 0,
                 total_task_time_ms: 0,
+
+                last_cpu_percent: None,
+                last_mem_bytes: None,
+                queue_depth: 0,
+                local_paths: req.local_paths,
             },
         );
     }
@@ -216,13 +526,43 @@ async fn worker_heartbeat(
     State(state): State<AppState>,
     Json(req): Json<WorkerHeartbeatRequest>,
 ) -> Result<Json<WorkerHeartbeatResponse>, StatusCode> {
-    let mut workers = state.workers.lock().unwrap();
-    if let Some(meta) = workers.get_mut(&req.worker_id) {
-        meta.last_heartbeat = std::time::SystemTime::now();
-        Ok(Json(WorkerHeartbeatResponse { ok: true }))
-    } else {
-        Err(StatusCode::NOT_FOUND)
+    let found = {
+        let mut workers = state.workers.lock().unwrap();
+        if let Some(meta) = workers.get_mut(&req.worker_id) {
+            meta.last_heartbeat = std::time::SystemTime::now();
+            meta.last_cpu_percent = Some(req.cpu_percent);
+            meta.last_mem_bytes = Some(req.mem_bytes);
+            meta.queue_depth = req.queue_depth;
+            true
+        } else {
+            false
+        }
+    };
+
+    if !found {
+        return Err(StatusCode::NOT_FOUND);
     }
+
+    // Jobs cancelados de los que este worker tiene (o tenía) alguna tarea
+    // en vuelo: se los mandamos en cada heartbeat para que pueda abortar
+    // su `ActiveTask` y limpiar el output parcial (ver `cancel_job`).
+    let cancelled_jobs: Vec<common::JobId> = {
+        let in_flight = state.in_flight.lock().unwrap();
+        let jobs = state.jobs.lock().unwrap();
+        in_flight
+            .values()
+            .filter(|inf| inf.worker_id == req.worker_id)
+            .filter_map(|inf| {
+                let job_id = &inf.task.job_id;
+                match jobs.get(job_id) {
+                    Some(j) if j.status == JobStatus::Cancelled => Some(job_id.clone()),
+                    _ => None,
+                }
+            })
+            .collect()
+    };
+
+    Ok(Json(WorkerHeartbeatResponse { ok: true, cancelled_jobs }))
 }
 
 // Asigna la siguiente tarea en cola (si hay)
@@ -230,47 +570,106 @@ async fn assign_task(
     State(state): State<AppState>,
     Json(req): Json<TaskAssignmentRequest>,
 ) -> Json<TaskAssignmentResponse> {
+    Json(TaskAssignmentResponse {
+        task: try_assign_task(&state, &req.worker_id),
+    })
+}
+
+/// Lógica real de asignación de una tarea a un worker puntual: mira
+/// capacidad/CPU, elige la próxima tarea (con preferencia por localidad)
+/// y la registra en `in_flight`. La usan tanto `assign_task` (poll de
+/// `/api/v1/tasks/next`) como `push_pending_tasks` (push por el stream
+/// de `/api/v1/workers/{id}/stream`), así ambos caminos respetan
+/// exactamente las mismas reglas de cupo.
+fn try_assign_task(state: &AppState, worker_id: &str) -> Option<common::Task> {
     // 1) Cuántas tareas tiene ya este worker en vuelo
     let active_for_worker: usize = {
         let in_flight = state.in_flight.lock().unwrap();
         in_flight
             .values()
-            .filter(|entry| entry.worker_id == req.worker_id)
+            .filter(|entry| entry.worker_id == worker_id)
             .count()
     };
 
-    // 2) Capacidad máxima de este worker (max_concurrency)
-    let max_for_worker: u32 = {
+    // 2) Capacidad máxima y carga reportada de este worker
+    let (max_for_worker, over_utilized, local_paths): (u32, bool, Vec<String>) = {
         let workers = state.workers.lock().unwrap();
-        workers
-            .get(&req.worker_id)
-            .map(|m| m.max_concurrency)
-            .unwrap_or(1) // por si el worker no existe por alguna razón
+        match workers.get(worker_id) {
+            Some(m) => (
+                m.max_concurrency,
+                m.last_cpu_percent
+                    .map(|cpu| cpu > MAX_WORKER_CPU_PERCENT)
+                    .unwrap_or(false),
+                m.local_paths.clone(),
+            ),
+            None => (1, false, Vec::new()), // por si el worker no existe por alguna razón
+        }
     };
 
     // Si ya está al tope, no le damos más tareas
     if active_for_worker as u32 >= max_for_worker {
         info!(
             "worker {} pidió tarea pero ya tiene {}/{} en vuelo",
-            req.worker_id, active_for_worker, max_for_worker
+            worker_id, active_for_worker, max_for_worker
+        );
+        return None;
+    }
+
+    // Si está reportando más CPU de la que nos parece sano, lo dejamos
+    // drenar lo que ya tiene antes de mandarle más, aunque le quede lugar
+    // según max_concurrency.
+    if over_utilized {
+        info!(
+            "worker {} pidió tarea pero está sobreutilizado (cpu>{:.0}%), no se le asigna nada esta vez",
+            worker_id, MAX_WORKER_CPU_PERCENT
         );
-        return Json(TaskAssignmentResponse { task: None });
+        return None;
     }
 
-    // 3) Sacar la siguiente tarea de la cola global
-    let task_opt = {
+    // 3) Elegir la próxima tarea: preferimos una cuyo input_path sea local
+    // al worker (evita mover datos de otro nodo); si ninguna lo es,
+    // tomamos la primera de la cola (FIFO), como antes. Las tareas de un
+    // job pausado se saltean sin sacarlas de la cola, así mantienen su
+    // posición para cuando se reanude el job.
+    let paused_jobs: std::collections::HashSet<common::JobId> = {
+        let jobs = state.jobs.lock().unwrap();
+        jobs.values()
+            .filter(|j| j.status == JobStatus::Paused)
+            .map(|j| j.id.clone())
+            .collect()
+    };
+
+    let (task_opt, was_local) = {
         let mut queue = state.tasks_queue.lock().unwrap();
-        queue.pop_front()
+        if local_paths.is_empty() {
+            match queue.iter().position(|t| !paused_jobs.contains(&t.job_id)) {
+                Some(idx) => (queue.remove(idx), false),
+                None => (None, false),
+            }
+        } else {
+            let local_idx = queue.iter().position(|t| {
+                !paused_jobs.contains(&t.job_id)
+                    && local_paths.iter().any(|p| t.input_path.starts_with(p.as_str()))
+            });
+            match local_idx {
+                Some(idx) => (queue.remove(idx), true),
+                None => match queue.iter().position(|t| !paused_jobs.contains(&t.job_id)) {
+                    Some(idx) => (queue.remove(idx), false),
+                    None => (None, false),
+                },
+            }
+        }
     };
 
     if let Some(ref t) = task_opt {
         info!(
-            "asignando tarea {} (job={}, input={} output={}) al worker {} ({}/{} en vuelo -> +1)",
+            "asignando tarea {} (job={}, input={} output={}, local={}) al worker {} ({}/{} en vuelo -> +1)",
             t.id,
             t.job_id,
             t.input_path,
             t.output_path,
-            req.worker_id,
+            was_local,
+            worker_id,
             active_for_worker,
             max_for_worker,
         );
@@ -282,11 +681,15 @@ async fn assign_task(
                 t.id.clone(),
                 InFlight {
                     task: t.clone(),
-                    worker_id: req.worker_id.clone(),
-                    started_at: SystemTime::now(),
+                    worker_id: worker_id.to_string(),
+                    dispatched_at: SystemTime::now(),
                 },
             );
         }
+        state.wal.append(WalEvent::TaskAssigned {
+            task_id: t.id.clone(),
+            worker_id: worker_id.to_string(),
+        });
 
         // 5) Actualizar el job: marcar como Running y setear started_at si es la primera vez
         {
@@ -304,18 +707,109 @@ async fn assign_task(
         // 6) Métricas del worker: incrementar tareas iniciadas
         {
             let mut workers = state.workers.lock().unwrap();
-            if let Some(meta) = workers.get_mut(&req.worker_id) {
+            if let Some(meta) = workers.get_mut(worker_id) {
                 meta.tasks_started += 1;
             }
         }
     } else {
-        info!(
-            "worker {} pidió tarea pero no hay tareas en cola",
-            req.worker_id
-        );
+        info!("worker {} pidió tarea pero no hay tareas en cola", worker_id);
     }
 
-    Json(TaskAssignmentResponse { task: task_opt })
+    task_opt
+}
+
+/// Stream de Server-Sent Events que el master usa para empujarle tareas
+/// a un worker apenas están disponibles, en vez de esperar a que el
+/// worker vuelva a pollear `/api/v1/tasks/next`. Mientras el worker
+/// mantenga la conexión abierta, `push_pending_tasks` le manda un evento
+/// JSON por cada `TaskAssignmentResponse`; si la conexión se corta, el
+/// worker sigue pudiendo recibir tareas por el poll de siempre.
+async fn worker_stream(
+    State(state): State<AppState>,
+    Path(worker_id): Path<String>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::unbounded_channel::<TaskAssignmentResponse>();
+    state.task_streams.lock().unwrap().insert(worker_id.clone(), tx);
+    info!("worker {} abrió el stream de push de tareas", worker_id);
+
+    let stream = TaskEventStream {
+        worker_id,
+        streams: state.task_streams.clone(),
+        rx,
+    };
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+struct TaskEventStream {
+    worker_id: WorkerId,
+    streams: Arc<Mutex<HashMap<WorkerId, mpsc::UnboundedSender<TaskAssignmentResponse>>>>,
+    rx: mpsc::UnboundedReceiver<TaskAssignmentResponse>,
+}
+
+impl Stream for TaskEventStream {
+    type Item = Result<Event, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.rx.poll_recv(cx) {
+            Poll::Ready(Some(resp)) => {
+                let event = Event::default().json_data(resp).unwrap_or_else(|e| {
+                    Event::default().comment(format!("error serializando tarea: {e}"))
+                });
+                Poll::Ready(Some(Ok(event)))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for TaskEventStream {
+    fn drop(&mut self) {
+        self.streams.lock().unwrap().remove(&self.worker_id);
+        info!("worker {}: se cerró el stream de push de tareas", self.worker_id);
+    }
+}
+
+/// Recorre los workers con un stream abierto y les empuja tareas
+/// mientras les quede cupo y haya algo en `tasks_queue`. Se llama cada
+/// vez que puede haber novedades: tareas nuevas encoladas (alta de job,
+/// próxima etapa materializada, reintento que venció) o un slot que se
+/// liberó (tarea completada).
+pub(crate) fn push_pending_tasks(state: &AppState) {
+    let candidate_worker_ids: Vec<WorkerId> = {
+        let streams = state.task_streams.lock().unwrap();
+        streams.keys().cloned().collect()
+    };
+
+    for worker_id in candidate_worker_ids {
+        loop {
+            if state.tasks_queue.lock().unwrap().is_empty() {
+                break;
+            }
+
+            let sender = {
+                let streams = state.task_streams.lock().unwrap();
+                match streams.get(&worker_id) {
+                    Some(tx) => tx.clone(),
+                    None => break,
+                }
+            };
+
+            let Some(task) = try_assign_task(state, &worker_id) else {
+                break;
+            };
+
+            if sender.send(TaskAssignmentResponse { task: Some(task) }).is_err() {
+                // El worker se desconectó justo ahora; la tarea ya quedó
+                // en in_flight, así que si nunca llega a ejecutarla el
+                // sweep de failover (DeadWorkerMonitor) la va a
+                // recuperar cuando dejen de llegar sus heartbeats.
+                state.task_streams.lock().unwrap().remove(&worker_id);
+                break;
+            }
+        }
+    }
 }
 
 // Worker reporta que terminó una tarea
@@ -329,6 +823,25 @@ async fn complete_task(
         in_flight.remove(&req.task_id)
     };
 
+    // Se liberó un slot de este worker (haya terminado bien o mal):
+    // si tiene el stream de push abierto y hay algo en cola, se lo
+    // mandamos ya mismo en vez de esperar su próximo poll.
+    push_pending_tasks(&state);
+
+    // La tarea ya no está en vuelo: su log deja de ser "activo" (el
+    // archivo sigue en disco, pero ya no hace falta reconstruirlo tras
+    // un reinicio del master).
+    {
+        let mut task_logs = state.task_logs.lock().unwrap();
+        if task_logs.remove(&req.task_id).is_some() {
+            crate::tasklog::write_active_index(&task_logs);
+        }
+    }
+
+    // Tarea ya no en vuelo: su progreso parcial deja paso a
+    // `completed_tasks` en `recompute_job_progress`.
+    state.task_progress.lock().unwrap().remove(&req.task_id);
+
     if let Some(inflight) = maybe_inflight {
         let mut task = inflight.task;
         let job_id = task.job_id.clone();
@@ -336,7 +849,7 @@ async fn complete_task(
 
         // ---- Métricas de worker: duración de la tarea ----
         let duration_ms: u64 = inflight
-            .started_at
+            .dispatched_at
             .elapsed()
             .map(|d| d.as_millis() as u64)
             .unwrap_or(0);
@@ -353,24 +866,115 @@ async fn complete_task(
             }
         }
 
-        // ---- Caso fallo: reintentos o marcar job como Failed ----
+        // ---- Carrera especulativa: si (job, stage, partition) ya tiene
+        // un ganador, esta es la copia perdedora (straggler original o
+        // duplicado especulativo) y se descarta sin tocar el job. ----
+        //
+        // El check y el "me quedo con esta partición" tienen que ser
+        // atómicos bajo el mismo lock: si dos copias completan con éxito
+        // casi al mismo tiempo, un check separado de un insert posterior
+        // (más abajo, en el camino de éxito) deja una ventana en la que
+        // ambas ven `contains == false`, ambas pasan, y terminan
+        // duplicando la entrada en `stage_outputs` y el contador de
+        // `completed_tasks`. Para una tarea fallida no hace falta marcar
+        // nada: sólo nos importa si la partición ya tiene un ganador.
+        let partition_key = (job_id.clone(), task.stage, task.partition);
+        let already_won = if req.success {
+            !state
+                .completed_partitions
+                .lock()
+                .unwrap()
+                .insert(partition_key.clone())
+        } else {
+            state
+                .completed_partitions
+                .lock()
+                .unwrap()
+                .contains(&partition_key)
+        };
+        if already_won {
+            info!(
+                "tarea {} ({:?}) llegó después de su copia ganadora, se descarta",
+                task.id, partition_key
+            );
+            // La tarea ganadora pudo haber encontrado el barrier abierto
+            // (esta, la perdedora, seguía in_flight) y no materializó la
+            // próxima etapa. Ahora que esta copia también salió de
+            // in_flight, hay que re-evaluar el barrier o el job se queda
+            // en Running para siempre.
+            try_materialize_stage_barrier(&state, &job_id, task.stage);
+            return Ok(Json(TaskCompleteResponse { ok: true }));
+        }
+
+        // ---- Job cancelado mientras la tarea estaba en vuelo: se
+        // descarta el resultado, no se reintenta y no se materializa la
+        // próxima etapa. ----
+        let job_cancelled = matches!(
+            state.jobs.lock().unwrap().get(&job_id).map(|j| j.status.clone()),
+            Some(JobStatus::Cancelled)
+        );
+        if job_cancelled {
+            info!("tarea {} llegó para el job {} ya cancelado, se descarta", task.id, job_id);
+            return Ok(Json(TaskCompleteResponse { ok: true }));
+        }
+
+        // ---- Caso fallo: reintentos con backoff o marcar job como Failed ----
         if !req.success {
-            if task.attempt + 1 <= MAX_TASK_ATTEMPTS {
+            state.wal.append(WalEvent::TaskCompleted { task_id: req.task_id.clone(), success: false });
+            let non_retryable = req.error_kind.is_some();
+
+            if !non_retryable && task.attempt + 1 <= MAX_TASK_ATTEMPTS {
+                let ready_at = SystemTime::now() + crate::retry::backoff_delay(task.attempt);
                 task.attempt += 1;
-                let mut queue = state.tasks_queue.lock().unwrap();
-                queue.push_back(task);
+                {
+                    let mut jobs = state.jobs.lock().unwrap();
+                    if let Some(job) = jobs.get_mut(&job_id) {
+                        job.retries += 1;
+                        state.wal.append(WalEvent::JobUpdated { job: job.clone() });
+                    }
+                }
+                info!(
+                    "tarea {} del job {} falló (attempt={}), reintentando en {:?}",
+                    task.id,
+                    job_id,
+                    task.attempt,
+                    ready_at.duration_since(SystemTime::now()).unwrap_or_default()
+                );
+                let mut retry_queue = state.retry_queue.lock().unwrap();
+                retry_queue.push(std::cmp::Reverse(crate::retry::DelayedTask { ready_at, task }));
             } else {
+                if let Some(kind) = &req.error_kind {
+                    info!(
+                        "tarea {} del job {} falló con error no-reintentable ({}), fallando el job",
+                        task.id, job_id, kind
+                    );
+                }
                 let mut jobs = state.jobs.lock().unwrap();
                 if let Some(job) = jobs.get_mut(&job_id) {
                     job.failed_tasks += 1;
                     job.status = JobStatus::Failed;
                     job.finished_at = Some(Utc::now());
+                    state.wal.append(WalEvent::JobUpdated { job: job.clone() });
                 }
             }
+            recompute_job_progress(&state, &job_id);
             return Ok(Json(TaskCompleteResponse { ok: true }));
         }
 
-        // ---- Caso éxito: contar tarea completada ----
+        // ---- Caso éxito: contar tarea completada y registrar su output
+        // para el shuffle hacia la próxima etapa (la partición ya quedó
+        // reservada más arriba, de forma atómica, junto con el check de
+        // `already_won`) ----
+        state.wal.append(WalEvent::TaskCompleted { task_id: req.task_id.clone(), success: true });
+
+        state
+            .stage_durations_ms
+            .lock()
+            .unwrap()
+            .entry((job_id.clone(), task.stage))
+            .or_insert_with(Vec::new)
+            .push(duration_ms);
+
         {
             let mut jobs = state.jobs.lock().unwrap();
             if let Some(job) = jobs.get_mut(&job_id) {
@@ -378,27 +982,16 @@ async fn complete_task(
             }
         }
 
-        // Ver si todavía quedan tareas de este job
-        let queue_has_for_job = {
-            let queue = state.tasks_queue.lock().unwrap();
-            queue.iter().any(|t| t.job_id == job_id)
-        };
-
-        let inflight_has_for_job = {
-            let inflight_map = state.in_flight.lock().unwrap();
-            inflight_map.values().any(|it| it.task.job_id == job_id)
-        };
-
-        if !queue_has_for_job && !inflight_has_for_job {
-            let mut jobs = state.jobs.lock().unwrap();
-            if let Some(job) = jobs.get_mut(&job_id) {
-                if !matches!(job.status, JobStatus::Failed) {
-                    job.status = JobStatus::Succeeded;
-                }
-                job.finished_at = Some(Utc::now());
-            }
+        {
+            let mut outputs = state.stage_outputs.lock().unwrap();
+            outputs
+                .entry((job_id.clone(), task.stage))
+                .or_insert_with(Vec::new)
+                .push((task.partition, task.output_path.clone()));
         }
 
+        try_materialize_stage_barrier(&state, &job_id, task.stage);
+
         Ok(Json(TaskCompleteResponse { ok: true }))
     } else {
         Err(StatusCode::NOT_FOUND)
@@ -406,6 +999,154 @@ async fn complete_task(
 }
 
 
+// El worker avisa que arrancó a ejecutar una tarea y con qué log id la va
+// a ir registrando (ver `common::task_log`). Lo guardamos en
+// `state.task_logs` (para servir `GET /api/v1/tasks/{id}/log`) y lo
+// persistimos en `active_tasks.json` para que sobreviva a un reinicio del
+// master.
+async fn task_started(
+    State(state): State<AppState>,
+    Json(req): Json<TaskStartedRequest>,
+) -> Json<TaskStartedResponse> {
+    let meta = {
+        let in_flight = state.in_flight.lock().unwrap();
+        in_flight.get(&req.task_id).map(|inf| (inf.task.job_id.clone(), inf.task.stage, inf.task.partition, inf.worker_id.clone()))
+    };
+
+    let Some((job_id, stage, partition, worker_id)) = meta else {
+        return Json(TaskStartedResponse { ok: false });
+    };
+
+    {
+        let mut task_logs = state.task_logs.lock().unwrap();
+        task_logs.insert(
+            req.task_id.clone(),
+            TaskLogMeta {
+                task_id: req.task_id.clone(),
+                job_id,
+                stage,
+                partition,
+                worker_id,
+                log_id: req.log_id.clone(),
+            },
+        );
+        crate::tasklog::write_active_index(&task_logs);
+    }
+
+    Json(TaskStartedResponse { ok: true })
+}
+
+/// El worker reporta avance incremental de una tarea en vuelo (ver
+/// `common::engine::PipelineState::progress`). Guardamos el último valor
+/// en `state.task_progress` y recomputamos `JobInfo::progress` del job al
+/// que pertenece, para que `client status` no muestre 0% hasta que la
+/// primera tarea termine.
+async fn task_progress(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+    Json(req): Json<TaskProgressRequest>,
+) -> Json<TaskProgressResponse> {
+    let job_id = {
+        let in_flight = state.in_flight.lock().unwrap();
+        in_flight.get(&task_id).map(|inf| inf.task.job_id.clone())
+    };
+
+    let Some(job_id) = job_id else {
+        return Json(TaskProgressResponse { ok: false });
+    };
+
+    {
+        let mut task_progress = state.task_progress.lock().unwrap();
+        task_progress.insert(
+            task_id,
+            TaskProgress {
+                processed_bytes: req.processed_bytes,
+                total_bytes: req.total_bytes,
+                processed_records: req.processed_records,
+            },
+        );
+    }
+
+    recompute_job_progress(&state, &job_id);
+
+    Json(TaskProgressResponse { ok: true })
+}
+
+/// Deriva `JobInfo::progress` para `job_id`: cada tarea ya terminada cuenta
+/// como 1.0, y cada tarea todavía en vuelo aporta la fracción de su último
+/// reporte de `state.task_progress` (0.0 si todavía no reportó ninguno).
+/// No hace nada si el job no existe o no tiene tareas (`total_tasks == 0`).
+fn recompute_job_progress(state: &AppState, job_id: &str) {
+    let in_flight_fraction: f32 = {
+        let in_flight = state.in_flight.lock().unwrap();
+        let task_progress = state.task_progress.lock().unwrap();
+        in_flight
+            .values()
+            .filter(|inf| inf.task.job_id == job_id)
+            .map(|inf| {
+                task_progress
+                    .get(&inf.task.id)
+                    .map(TaskProgress::fraction)
+                    .unwrap_or(0.0)
+            })
+            .sum()
+    };
+
+    let mut jobs = state.jobs.lock().unwrap();
+    if let Some(job) = jobs.get_mut(job_id) {
+        if job.total_tasks > 0 {
+            job.progress = ((job.completed_tasks as f32 + in_flight_fraction)
+                / job.total_tasks as f32)
+                .clamp(0.0, 1.0);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TaskLogQuery {
+    /// Byte desde el que seguir leyendo (para tailing incremental).
+    #[serde(default)]
+    offset: u64,
+    /// No cambia la respuesta en sí -- el servidor siempre devuelve lo que
+    /// haya a partir de `offset`; `follow=true` es lo que le indica al
+    /// `client logs` que debe seguir pidiendo con el `next_offset` devuelto
+    /// en vez de mostrar el log una sola vez.
+    #[serde(default)]
+    #[allow(dead_code)]
+    follow: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct TaskLogResponse {
+    content: String,
+    next_offset: u64,
+}
+
+// Sirve el log de una tarea (identificado por `task_id`, el mismo id
+// estable de `Task::id`) a partir de `?offset=N`. Resuelve el `log_id` del
+// intento más reciente vía `state.task_logs`; si la tarea no está ahí
+// (nunca arrancó, ya completó y `complete_task` la sacó del mapa, o el
+// índice se perdió) devuelve 404.
+async fn get_task_log(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+    axum::extract::Query(q): axum::extract::Query<TaskLogQuery>,
+) -> Result<Json<TaskLogResponse>, StatusCode> {
+    let log_id = {
+        let task_logs = state.task_logs.lock().unwrap();
+        task_logs.get(&task_id).map(|m| m.log_id.clone())
+    };
+
+    let Some(log_id) = log_id else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    match common::task_log::read_from(&log_id, q.offset) {
+        Ok((content, next_offset)) => Ok(Json(TaskLogResponse { content, next_offset })),
+        Err(_) => Err(StatusCode::NOT_FOUND),
+    }
+}
+
 async fn list_workers(State(state): State<AppState>) -> Json<Vec<WorkerMetrics>> {
     let now = SystemTime::now();
 
@@ -434,10 +1175,19 @@ async fn list_workers(State(state): State<AppState>) -> Json<Vec<WorkerMetrics>>
             None
         };
 
+        let state = if meta.dead {
+            WorkerActivityState::Dead
+        } else if active > 0 {
+            WorkerActivityState::Active
+        } else {
+            WorkerActivityState::Idle
+        };
+
         out.push(WorkerMetrics {
             worker_id: wid.clone(),
             hostname: meta.hostname.clone(),
             dead: meta.dead,
+            state,
             max_concurrency: meta.max_concurrency,
             last_heartbeat_secs_ago: age_secs,
             active_tasks: active,
@@ -445,11 +1195,60 @@ async fn list_workers(State(state): State<AppState>) -> Json<Vec<WorkerMetrics>>
             tasks_succeeded: meta.tasks_succeeded,
             tasks_failed: meta.tasks_failed,
             avg_task_ms: avg_ms,
+            cpu_percent: meta.last_cpu_percent,
+            mem_bytes: meta.last_mem_bytes,
+            queue_depth: Some(meta.queue_depth),
         });
     }
 
     Json(out)
 }
 
+async fn list_background_workers(State(state): State<AppState>) -> Json<Vec<BackgroundWorkerInfo>> {
+    Json(state.background.snapshot())
+}
+
+/// Pausa un worker en segundo plano (`DeadWorkerMonitor`, `RetryDrainWorker`,
+/// etc.): deja de hacer `step`, pero sigue registrado y visible en
+/// `GET /api/v1/background`. 404 si no existe un worker con ese nombre.
+async fn pause_background_worker(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> StatusCode {
+    if state.background.send_control(&name, Control::Pause) {
+        info!("background worker {} pausado", name);
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Reanuda un worker en segundo plano previamente pausado.
+async fn resume_background_worker(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> StatusCode {
+    if state.background.send_control(&name, Control::Resume) {
+        info!("background worker {} reanudado", name);
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Cancela un worker en segundo plano: deja de correr para siempre (no
+/// hay forma de reanudarlo después). Queda en `BackgroundWorkerInfo` con
+/// `state: Dead` hasta que el master reinicie.
+async fn cancel_background_worker(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> StatusCode {
+    if state.background.send_control(&name, Control::Cancel) {
+        info!("background worker {} cancelado", name);
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
 
 
@@ -1,10 +1,23 @@
 // master/src/main.rs
 
+mod background;
 mod handlers;
 mod state;
+mod schedule;
+mod scheduler;
+mod retry;
 mod failover; // 👈 importante
+mod wal;
+mod tasklog;
 
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::failover::{DeadWorkerMonitor, RetryDrainWorker, StragglerDetector};
+use crate::scheduler::JobScheduler;
 use crate::state::AppState;
+use crate::wal::{Wal, WalCompactor};
 use tokio::net::TcpListener;
 use tracing::info;
 
@@ -12,6 +25,13 @@ use tracing::info;
 pub const MAX_TASK_ATTEMPTS: u32 = 3;
 pub const WORKER_HEARTBEAT_TIMEOUT_SECS: u64 = 3;
 pub const FAILOVER_SWEEP_INTERVAL_SECS: u64 = 3;
+pub const WAL_COMPACTION_INTERVAL_SECS: u64 = 60;
+/// Cada cuánto revisa el scheduler de jobs recurrentes si hay alguna
+/// entrada vencida (ver `scheduler::JobScheduler`).
+pub const JOB_SCHEDULER_TICK_SECS: u64 = 1;
+/// Por encima de este uso de CPU reportado, un worker deja de recibir
+/// tareas nuevas aunque todavía tenga lugar según `max_concurrency`.
+pub const MAX_WORKER_CPU_PERCENT: f32 = 90.0;
 // ----------------------------------------------------
 
 #[tokio::main]
@@ -20,17 +40,39 @@ async fn main() {
         .with_env_filter("master=debug,axum=info")
         .init();
 
-    // Estado compartido del master
-    let state = AppState::new();
+    // `DATABASE_PATH` es el nombre "genérico" que usan otras piezas del
+    // proyecto para la ruta del store persistente del master; `MASTER_WAL_PATH`
+    // sigue aceptado por compatibilidad con despliegues existentes.
+    let wal_path = env::var("DATABASE_PATH")
+        .or_else(|_| env::var("MASTER_WAL_PATH"))
+        .unwrap_or_else(|_| "master_wal.jsonl".to_string());
+    let wal = Arc::new(Wal::open(&wal_path).expect("no se pudo abrir el write-ahead log"));
+
+    // Estado compartido del master, reconstruido a partir del WAL si el
+    // coordinador ya había corrido antes.
+    let state = AppState::new(wal.clone());
+    wal.replay_into(&state);
 
     // Router HTTP
     let app = handlers::build_router(state.clone());
 
-    // Loop de failover / heartbeats en segundo plano
-    let failover_state = state.clone();
-    tokio::spawn(async move {
-        failover::run_failover_loop(failover_state).await;
-    });
+    // Workers de mantenimiento en segundo plano: cada uno corre en su
+    // propia tarea de tokio y puede pausarse/reanudarse/cancelarse vía
+    // `state.background` (ver GET /api/v1/background).
+    let tick = Duration::from_secs(FAILOVER_SWEEP_INTERVAL_SECS);
+    state.background.spawn(Box::new(DeadWorkerMonitor::new()), state.clone(), tick);
+    state.background.spawn(Box::new(RetryDrainWorker), state.clone(), tick);
+    state.background.spawn(Box::new(StragglerDetector), state.clone(), tick);
+    state.background.spawn(
+        Box::new(WalCompactor),
+        state.clone(),
+        Duration::from_secs(WAL_COMPACTION_INTERVAL_SECS),
+    );
+    state.background.spawn(
+        Box::new(JobScheduler),
+        state.clone(),
+        Duration::from_secs(JOB_SCHEDULER_TICK_SECS),
+    );
 
     // Servidor HTTP
     let listener = TcpListener::bind("0.0.0.0:8080")
@@ -0,0 +1,88 @@
+// master/src/retry.rs
+//
+// Cola de reintentos con backoff exponencial: en vez de reencolar una
+// tarea fallida de inmediato (lo que hot-loopea una tarea que falla
+// siempre), la guardamos acá con un `ready_at` futuro y el loop de
+// failover la drena hacia `tasks_queue` cuando le toca.
+
+use std::cmp::Ordering;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use common::Task;
+
+pub const BASE_DELAY: Duration = Duration::from_secs(1);
+pub const MAX_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct DelayedTask {
+    pub ready_at: SystemTime,
+    pub task: Task,
+}
+
+impl PartialEq for DelayedTask {
+    fn eq(&self, other: &Self) -> bool {
+        self.ready_at == other.ready_at
+    }
+}
+impl Eq for DelayedTask {}
+
+impl PartialOrd for DelayedTask {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DelayedTask {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Orden ascendente por ready_at: el usado junto con `Reverse<_>`
+        // en un BinaryHeap da un min-heap (el más próximo sale primero).
+        self.ready_at.cmp(&other.ready_at)
+    }
+}
+
+/// Jitter pseudo-aleatorio en el rango [-max, max], sin depender de una
+/// crate externa: usamos los nanosegundos del reloj como fuente de ruido.
+fn jitter_ms(max_ms: u64) -> i64 {
+    if max_ms == 0 {
+        return 0;
+    }
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos() as u64;
+    (nanos % (max_ms * 2 + 1)) as i64 - max_ms as i64
+}
+
+/// Calcula el delay antes del próximo intento: `base * 2^attempt`, con
+/// un techo en `MAX_DELAY` y un jitter de hasta el ±20% para no generar
+/// un thundering herd cuando muchas tareas fallan juntas (ej: al caerse
+/// un worker con varias tareas en vuelo).
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let base_ms = BASE_DELAY.as_millis() as u64;
+    let cap_ms = MAX_DELAY.as_millis() as u64;
+
+    let exp_ms = base_ms
+        .saturating_mul(1u64.checked_shl(attempt.min(20)).unwrap_or(u64::MAX))
+        .min(cap_ms);
+
+    let jitter_max = exp_ms / 5; // 20%
+    let jittered = (exp_ms as i64 + jitter_ms(jitter_max)).max(0) as u64;
+
+    Duration::from_millis(jittered.min(cap_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_delay_crece_y_respeta_el_techo() {
+        let d0 = backoff_delay(0);
+        let d3 = backoff_delay(3);
+        let d_huge = backoff_delay(50);
+
+        assert!(d0 >= Duration::from_millis(800)); // ~1s con jitter
+        assert!(d3 > d0);
+        assert!(d_huge <= MAX_DELAY + Duration::from_millis(MAX_DELAY.as_millis() as u64 / 5));
+    }
+}
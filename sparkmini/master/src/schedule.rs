@@ -0,0 +1,146 @@
+// master/src/schedule.rs
+//
+// Convierte el Dag que llega en el JobRequest en un plan de etapas (stages)
+// que el coordinador puede ejecutar de a una por vez, con un shuffle entre
+// etapas consecutivas.
+
+use std::collections::HashMap;
+
+use common::dag::topo_sort;
+use common::Dag;
+
+/// Una etapa lógica del job: un conjunto de nodos del DAG que se ejecutan
+/// juntos dentro de una misma tarea (sin shuffle de por medio), seguidos
+/// opcionalmente de un operador "ancho" (reduce_by_key / join /
+/// aggregate_by_key) que es el
+/// que da el límite de la etapa.
+#[derive(Debug, Clone)]
+pub struct StageNode {
+    pub stage: u32,
+    pub node_ids: Vec<String>,
+    pub parallelism: u32,
+}
+
+// aggregate_by_key agrupa por clave igual que reduce_by_key (generaliza
+// su `sum` fijo a cualquier combinación de agregaciones por campo, ver
+// `engine::op_aggregate_by_key`), así que necesita el mismo shuffle entre
+// etapas para que las claves iguales terminen en la misma partición.
+const WIDE_OPS: &[&str] = &["reduce_by_key", "join", "aggregate_by_key"];
+
+/// Agrupa el DAG (ya en orden topológico) en etapas separadas por los
+/// operadores "anchos" (los que necesitan shuffle: reduce_by_key, join,
+/// aggregate_by_key).
+/// Cada operador ancho cierra la etapa en la que aparece; lo que venga
+/// después arranca una etapa nueva.
+pub fn plan_stages(dag: &Dag, default_parallelism: u32) -> Vec<StageNode> {
+    let order = topo_sort(dag);
+    let by_id: HashMap<&str, &common::DagNode> =
+        dag.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+
+    let mut stages: Vec<StageNode> = Vec::new();
+    let mut current_ids: Vec<String> = Vec::new();
+    let mut current_parallelism = default_parallelism;
+    let mut stage_idx: u32 = 0;
+
+    for id in order {
+        let node = match by_id.get(id.as_str()) {
+            Some(n) => *n,
+            None => continue,
+        };
+
+        current_ids.push(node.id.clone());
+        if let Some(p) = node.partitions {
+            current_parallelism = p.max(1);
+        }
+
+        if WIDE_OPS.contains(&node.op.as_str()) {
+            stages.push(StageNode {
+                stage: stage_idx,
+                node_ids: std::mem::take(&mut current_ids),
+                parallelism: current_parallelism,
+            });
+            stage_idx += 1;
+            current_parallelism = default_parallelism;
+        }
+    }
+
+    if !current_ids.is_empty() {
+        stages.push(StageNode {
+            stage: stage_idx,
+            node_ids: current_ids,
+            parallelism: current_parallelism,
+        });
+    }
+
+    if stages.is_empty() {
+        // DAG vacío o sin nodos: una única etapa "vacía" para no romper
+        // el resto del flujo de create_job.
+        stages.push(StageNode {
+            stage: 0,
+            node_ids: Vec::new(),
+            parallelism: default_parallelism,
+        });
+    }
+
+    stages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::DagNode;
+
+    fn node(id: &str, op: &str) -> DagNode {
+        DagNode {
+            id: id.to_string(),
+            op: op.to_string(),
+            path: None,
+            partitions: None,
+            fn_name: None,
+            key: None,
+            fn_src: None,
+        }
+    }
+
+    #[test]
+    fn plan_stages_wordcount_termina_en_una_sola_etapa_ancha() {
+        let dag = Dag {
+            nodes: vec![
+                node("read", "read_text"),
+                node("flat", "flat_map"),
+                node("map1", "map"),
+                node("agg", "reduce_by_key"),
+            ],
+            edges: vec![
+                ("read".into(), "flat".into()),
+                ("flat".into(), "map1".into()),
+                ("map1".into(), "agg".into()),
+            ],
+        };
+
+        let stages = plan_stages(&dag, 4);
+        assert_eq!(stages.len(), 1);
+        assert_eq!(stages[0].stage, 0);
+        assert_eq!(stages[0].node_ids, vec!["read", "flat", "map1", "agg"]);
+    }
+
+    #[test]
+    fn plan_stages_separa_en_dos_etapas_tras_un_join() {
+        let dag = Dag {
+            nodes: vec![
+                node("read", "read_csv"),
+                node("join1", "join"),
+                node("map2", "map"),
+            ],
+            edges: vec![
+                ("read".into(), "join1".into()),
+                ("join1".into(), "map2".into()),
+            ],
+        };
+
+        let stages = plan_stages(&dag, 2);
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].node_ids, vec!["read", "join1"]);
+        assert_eq!(stages[1].node_ids, vec!["map2"]);
+    }
+}
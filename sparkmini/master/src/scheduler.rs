@@ -0,0 +1,389 @@
+// master/src/scheduler.rs
+//
+// Jobs recurrentes: a diferencia de `schedule.rs` (que parte un Dag en
+// etapas para un job puntual), acá lo que se agenda es la re-ejecución
+// periódica de un job completo, por intervalo fijo o por expresión cron.
+// Cada entrada vive en `AppState::schedules` y un `BackgroundWorker`
+// (`JobScheduler`) la revisa cada segundo, materializando un job nuevo
+// con la misma lógica que usa `POST /api/v1/jobs` (`handlers::submit_job`)
+// cuando le toca correr.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use common::{Dag, JobRequest, JobStatus, ScheduleId, ScheduleInfo, ScheduleRequest};
+use tracing::{info, warn};
+
+use crate::background::{BackgroundWorker, WorkerState};
+use crate::handlers::submit_job;
+use crate::state::AppState;
+
+/// Cómo se recalcula `next_run` cada vez que el schedule dispara.
+#[derive(Debug, Clone)]
+pub enum ScheduleSpec {
+    /// Corre cada `Duration` a partir de la última corrida.
+    Interval(Duration),
+    /// Expresión cron de 5 campos ("min hour dom month dow"), en UTC.
+    Cron(String),
+}
+
+impl ScheduleSpec {
+    fn next_run_after(&self, now: SystemTime) -> SystemTime {
+        match self {
+            ScheduleSpec::Interval(d) => now + *d,
+            ScheduleSpec::Cron(expr) => next_cron_run(expr, DateTime::<Utc>::from(now)),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            ScheduleSpec::Interval(d) => format!("every {}s", d.as_secs()),
+            ScheduleSpec::Cron(expr) => format!("cron: {}", expr),
+        }
+    }
+}
+
+/// Una entrada de job recurrente registrada en el master.
+#[derive(Debug, Clone)]
+pub struct ScheduleEntry {
+    pub id: ScheduleId,
+    pub name: String,
+    pub dag: Dag,
+    pub parallelism: u32,
+    pub input_glob: String,
+    pub output_dir: String,
+    pub spec: ScheduleSpec,
+    pub next_run: SystemTime,
+    pub last_job: Option<String>,
+}
+
+pub type SchedulesMap = HashMap<ScheduleId, ScheduleEntry>;
+
+fn to_info(entry: &ScheduleEntry) -> ScheduleInfo {
+    let next_run_secs = entry
+        .next_run
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    ScheduleInfo {
+        id: entry.id.clone(),
+        name: entry.name.clone(),
+        parallelism: entry.parallelism,
+        input_glob: entry.input_glob.clone(),
+        output_dir: entry.output_dir.clone(),
+        spec: entry.spec.describe(),
+        next_run_secs,
+        last_job: entry.last_job.clone(),
+    }
+}
+
+/* ---------------- handlers HTTP ---------------- */
+
+pub(crate) async fn create_schedule(
+    State(state): State<AppState>,
+    Json(req): Json<ScheduleRequest>,
+) -> Result<Json<ScheduleInfo>, StatusCode> {
+    let spec = match (req.interval_secs, req.cron) {
+        (Some(secs), None) => ScheduleSpec::Interval(Duration::from_secs(secs)),
+        (None, Some(expr)) => ScheduleSpec::Cron(expr),
+        _ => {
+            warn!("ScheduleRequest debe traer exactamente uno de interval_secs/cron");
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    let now = SystemTime::now();
+    let next_run = spec.next_run_after(now);
+
+    let entry = ScheduleEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: req.name,
+        dag: req.dag,
+        parallelism: req.parallelism,
+        input_glob: req.input_glob,
+        output_dir: req.output_dir,
+        spec,
+        next_run,
+        last_job: None,
+    };
+
+    let info = to_info(&entry);
+    state.schedules.lock().unwrap().insert(entry.id.clone(), entry);
+
+    Ok(Json(info))
+}
+
+pub(crate) async fn list_schedules(State(state): State<AppState>) -> Json<Vec<ScheduleInfo>> {
+    let schedules = state.schedules.lock().unwrap();
+    let mut out: Vec<ScheduleInfo> = schedules.values().map(to_info).collect();
+    out.sort_by(|a, b| a.id.cmp(&b.id));
+    Json(out)
+}
+
+pub(crate) async fn delete_schedule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> StatusCode {
+    let removed = state.schedules.lock().unwrap().remove(&id).is_some();
+    if removed {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/* ---------------- tick en segundo plano ---------------- */
+
+/// Background worker que cada segundo revisa los schedules vencidos y
+/// dispara una corrida nueva para cada uno, salvo que la corrida
+/// anterior (`last_job`) siga activa.
+pub struct JobScheduler;
+
+impl BackgroundWorker for JobScheduler {
+    fn name(&self) -> &str {
+        "job_scheduler"
+    }
+
+    fn step<'a>(
+        &'a mut self,
+        state: &'a AppState,
+    ) -> Pin<Box<dyn Future<Output = WorkerState> + Send + 'a>> {
+        Box::pin(async move {
+            tick_schedules(state);
+            WorkerState::Active
+        })
+    }
+}
+
+fn tick_schedules(state: &AppState) {
+    let now = SystemTime::now();
+
+    let due_ids: Vec<ScheduleId> = {
+        let schedules = state.schedules.lock().unwrap();
+        schedules
+            .values()
+            .filter(|e| e.next_run <= now)
+            .map(|e| e.id.clone())
+            .collect()
+    };
+
+    for id in due_ids {
+        let (req, still_running) = {
+            let schedules = state.schedules.lock().unwrap();
+            let Some(entry) = schedules.get(&id) else {
+                continue;
+            };
+
+            let still_running = match &entry.last_job {
+                Some(job_id) => {
+                    let jobs = state.jobs.lock().unwrap();
+                    jobs.get(job_id)
+                        .map(|j| matches!(j.status, JobStatus::Accepted | JobStatus::Running))
+                        .unwrap_or(false)
+                }
+                None => false,
+            };
+
+            let req = JobRequest {
+                name: entry.name.clone(),
+                dag: entry.dag.clone(),
+                parallelism: entry.parallelism,
+                input_glob: entry.input_glob.clone(),
+                output_dir: entry.output_dir.clone(),
+            };
+
+            (req, still_running)
+        };
+
+        if still_running {
+            info!(
+                "schedule {}: salteo esta corrida, el job anterior todavía no termina",
+                id
+            );
+            continue;
+        }
+
+        let job_info = submit_job(state, req);
+        info!("schedule {}: disparé job {}", id, job_info.id);
+
+        let mut schedules = state.schedules.lock().unwrap();
+        if let Some(entry) = schedules.get_mut(&id) {
+            entry.last_job = Some(job_info.id);
+            entry.next_run = entry.spec.next_run_after(SystemTime::now());
+        }
+    }
+}
+
+/* ---------------- parser de cron minimalista ---------------- */
+//
+// No hay forma de declarar una dependencia nueva en este repo (no existe
+// Cargo.toml), así que en vez de un crate de cron completo soportamos el
+// subconjunto de 5 campos más común: "*", "*/N", listas separadas por
+// coma y rangos "a-b", combinables ("1-5,10,*/2").
+
+fn parse_cron_field(s: &str, min: u32, max: u32) -> Vec<u32> {
+    if s == "*" {
+        return (min..=max).collect();
+    }
+
+    if let Some(step_str) = s.strip_prefix("*/") {
+        if let Ok(step) = step_str.parse::<u32>() {
+            if step > 0 {
+                return (min..=max).step_by(step as usize).collect();
+            }
+        }
+        return (min..=max).collect();
+    }
+
+    let mut out: Vec<u32> = Vec::new();
+    for part in s.split(',') {
+        if let Some((a, b)) = part.split_once('-') {
+            if let (Ok(a), Ok(b)) = (a.parse::<u32>(), b.parse::<u32>()) {
+                for v in a..=b {
+                    if v >= min && v <= max {
+                        out.push(v);
+                    }
+                }
+                continue;
+            }
+        }
+        if let Ok(v) = part.parse::<u32>() {
+            if v >= min && v <= max {
+                out.push(v);
+            }
+        }
+    }
+
+    if out.is_empty() {
+        (min..=max).collect()
+    } else {
+        out
+    }
+}
+
+fn matches_cron(
+    dt: &DateTime<Utc>,
+    minute: &[u32],
+    hour: &[u32],
+    dom: &[u32],
+    month: &[u32],
+    dow: &[u32],
+) -> bool {
+    minute.contains(&dt.minute())
+        && hour.contains(&dt.hour())
+        && dom.contains(&dt.day())
+        && month.contains(&dt.month())
+        && dow.contains(&dt.weekday().num_days_from_sunday())
+}
+
+/// Próxima corrida estrictamente posterior a `after` que matchee la
+/// expresión cron (5 campos: minuto hora dia-del-mes mes dia-de-semana,
+/// este último 0=domingo). Busca minuto a minuto hasta un año hacia
+/// adelante; si la expresión es inválida o no matchea nunca, reintenta
+/// en 1 minuto (o en 1 día si se agota la búsqueda) para no bloquear el
+/// resto de los schedules.
+fn next_cron_run(expr: &str, after: DateTime<Utc>) -> SystemTime {
+    let parts: Vec<&str> = expr.split_whitespace().collect();
+    if parts.len() != 5 {
+        warn!("expresión cron inválida '{}', reintento en 1 minuto", expr);
+        return SystemTime::from(after + chrono::Duration::minutes(1));
+    }
+
+    let minute = parse_cron_field(parts[0], 0, 59);
+    let hour = parse_cron_field(parts[1], 0, 23);
+    let dom = parse_cron_field(parts[2], 1, 31);
+    let month = parse_cron_field(parts[3], 1, 12);
+    let dow = parse_cron_field(parts[4], 0, 6);
+
+    let mut candidate = (after + chrono::Duration::minutes(1))
+        .with_second(0)
+        .unwrap()
+        .with_nanosecond(0)
+        .unwrap();
+
+    const MAX_MINUTES_AHEAD: u32 = 366 * 24 * 60;
+    for _ in 0..MAX_MINUTES_AHEAD {
+        if matches_cron(&candidate, &minute, &hour, &dom, &month, &dow) {
+            return SystemTime::from(candidate);
+        }
+        candidate += chrono::Duration::minutes(1);
+    }
+
+    warn!(
+        "cron '{}' no matcheó ninguna fecha dentro de un año, reintento en 1 día",
+        expr
+    );
+    SystemTime::from(after + chrono::Duration::days(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn dt(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn next_cron_run_hora_fija() {
+        // "30 4 * * *": todos los días a las 04:30.
+        let after = dt(2024, 3, 1, 10, 0);
+        let next = DateTime::<Utc>::from(next_cron_run("30 4 * * *", after));
+        assert_eq!(next, dt(2024, 3, 2, 4, 30));
+    }
+
+    #[test]
+    fn next_cron_run_step_de_minutos() {
+        // "*/15 * * * *": cada 15 minutos en punto.
+        let after = dt(2024, 3, 1, 10, 5);
+        let next = DateTime::<Utc>::from(next_cron_run("*/15 * * * *", after));
+        assert_eq!(next, dt(2024, 3, 1, 10, 15));
+    }
+
+    #[test]
+    fn next_cron_run_rango_y_lista() {
+        // "0 9-17,20 * * 1-5": en punto, de 9 a 17 o a las 20, lunes a viernes.
+        // 2024-03-01 es viernes; después de las 17:30 el próximo match es
+        // las 20:00 del mismo viernes.
+        let after = dt(2024, 3, 1, 17, 30);
+        let next = DateTime::<Utc>::from(next_cron_run("0 9-17,20 * * 1-5", after));
+        assert_eq!(next, dt(2024, 3, 1, 20, 0));
+
+        // Pasadas las 20:00 del viernes, el próximo es el lunes a las 9:00
+        // (sábado y domingo quedan fuera del rango 1-5).
+        let after_finde = dt(2024, 3, 1, 20, 30);
+        let next_lunes = DateTime::<Utc>::from(next_cron_run("0 9-17,20 * * 1-5", after_finde));
+        assert_eq!(next_lunes, dt(2024, 3, 4, 9, 0));
+    }
+
+    #[test]
+    fn next_cron_run_expresion_invalida_reintenta_en_un_minuto() {
+        let after = dt(2024, 3, 1, 10, 0);
+        let next = DateTime::<Utc>::from(next_cron_run("demasiado pocos campos", after));
+        assert_eq!(next, after + chrono::Duration::minutes(1));
+    }
+
+    #[test]
+    fn next_cron_run_insatisfacible_reintenta_en_un_dia() {
+        // El 31 de febrero no existe nunca, así que la búsqueda de un año
+        // no encuentra nada y cae al fallback de reintentar en 1 día.
+        let after = dt(2024, 3, 1, 10, 0);
+        let next = DateTime::<Utc>::from(next_cron_run("0 0 31 2 *", after));
+        assert_eq!(next, after + chrono::Duration::days(1));
+    }
+
+    #[test]
+    fn parse_cron_field_step_y_rango_con_lista() {
+        assert_eq!(parse_cron_field("*/15", 0, 59), vec![0, 15, 30, 45]);
+        assert_eq!(parse_cron_field("9-11,20", 0, 23), vec![9, 10, 11, 20]);
+        // Valores fuera de rango o no parseables hacen que el campo caiga
+        // a "cualquier valor" en vez de bloquear el schedule entero.
+        assert_eq!(parse_cron_field("nope", 1, 3), vec![1, 2, 3]);
+    }
+}
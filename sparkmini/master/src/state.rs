@@ -1,11 +1,20 @@
 // master/src/state.rs
 
-use common::{JobId, JobInfo, Task, TaskId, WorkerId};
+use common::{JobId, JobInfo, Task, TaskAssignmentResponse, TaskId, WorkerId};
 use std::{
-    collections::{HashMap, VecDeque},
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
     sync::{Arc, Mutex},
     time::SystemTime,
 };
+use tokio::sync::mpsc;
+
+use crate::background::BackgroundManager;
+use crate::retry::DelayedTask;
+use crate::schedule::StageNode;
+use crate::scheduler::ScheduleEntry;
+use crate::wal::Wal;
+use common::ScheduleId;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -14,16 +23,119 @@ pub struct AppState {
     pub in_flight: Arc<Mutex<HashMap<TaskId, InFlight>>>,
     pub workers: Arc<Mutex<HashMap<WorkerId, WorkerMeta>>>,
     pub rr_cursor: Arc<Mutex<usize>>,
+
+    /// Etapas del DAG que todavía no se materializaron en tareas,
+    /// indexadas por job. `stages[0]` ya se encoló en create_job;
+    /// acá solo quedan las pendientes (stage >= 1).
+    pub pending_stages: Arc<Mutex<HashMap<JobId, Vec<StageNode>>>>,
+
+    /// Outputs ya producidos por cada (job, stage), para poder armar
+    /// el shuffle hacia la próxima etapa: (partition de origen, output_path).
+    pub stage_outputs: Arc<Mutex<HashMap<(JobId, u32), Vec<(u32, String)>>>>,
+
+    /// Tareas que fallaron y están esperando su próximo intento con
+    /// backoff exponencial, en vez de volver directo a `tasks_queue`.
+    pub retry_queue: Arc<Mutex<BinaryHeap<Reverse<DelayedTask>>>>,
+
+    /// Duraciones (ms) de las tareas que ya completaron con éxito, por
+    /// (job, stage), usadas como baseline para detectar stragglers.
+    pub stage_durations_ms: Arc<Mutex<HashMap<(JobId, u32), Vec<u64>>>>,
+
+    /// (job, stage, partition) para las que ya se lanzó una copia
+    /// especulativa, para no lanzar más de una.
+    pub speculated: Arc<Mutex<HashSet<(JobId, u32, u32)>>>,
+
+    /// (job, stage, partition) cuya primera copia exitosa ya fue
+    /// contabilizada; cualquier otra copia que reporte después (la
+    /// perdedora de la carrera especulativa) se descarta.
+    pub completed_partitions: Arc<Mutex<HashSet<(JobId, u32, u32)>>>,
+
+    /// Registro de workers de mantenimiento en segundo plano (failover,
+    /// reintentos, stragglers), con visibilidad y control individual.
+    pub background: BackgroundManager,
+
+    /// Write-ahead log: permite reconstruir jobs/tareas/workers si el
+    /// coordinador se reinicia.
+    pub wal: Arc<Wal>,
+
+    /// Jobs recurrentes (por intervalo o cron), materializados en jobs
+    /// nuevos por el background worker `scheduler::JobScheduler`.
+    pub schedules: Arc<Mutex<HashMap<ScheduleId, ScheduleEntry>>>,
+
+    /// Canal de push hacia cada worker que tiene abierto
+    /// `GET /api/v1/workers/{id}/stream`. Mientras el canal esté presente,
+    /// `handlers::push_pending_tasks` le manda tareas apenas entran a
+    /// `tasks_queue` o se libera un slot, en vez de esperar a que el
+    /// worker vuelva a pollear `/api/v1/tasks/next`.
+    pub task_streams: Arc<Mutex<HashMap<WorkerId, mpsc::UnboundedSender<TaskAssignmentResponse>>>>,
+
+    /// Log id (ver `common::task_log`) de cada tarea actualmente en vuelo,
+    /// para poder resolver `GET /api/v1/tasks/{id}/log` y reconstruir
+    /// `active_tasks.json` (ver `crate::tasklog`).
+    pub task_logs: Arc<Mutex<HashMap<TaskId, TaskLogMeta>>>,
+
+    /// Último progreso reportado (`POST /api/v1/tasks/{id}/progress`) de
+    /// cada tarea en vuelo, usado para derivar `JobInfo::progress` (ver
+    /// `handlers::recompute_job_progress`). Se descarta apenas la tarea
+    /// completa, ganadora o no.
+    pub task_progress: Arc<Mutex<HashMap<TaskId, TaskProgress>>>,
+}
+
+/// Último progreso incremental reportado por una tarea en vuelo.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TaskProgress {
+    pub processed_bytes: u64,
+    pub total_bytes: u64,
+    pub processed_records: u64,
+}
+
+impl TaskProgress {
+    /// Fracción completada (0.0-1.0) a partir de bytes procesados sobre el
+    /// total conocido de antemano (`fs::metadata` del input). Si no se
+    /// conoce el total todavía, se asume 0 en vez de dividir por cero.
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes == 0 {
+            0.0
+        } else {
+            (self.processed_bytes as f32 / self.total_bytes as f32).clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// Metadata mínima para ubicar el log de una tarea en vuelo: basta con el
+/// `log_id` para abrir el archivo (ver `common::task_log::log_path`); el
+/// resto es sólo para que `active_tasks.json` sea legible sin tener que
+/// cruzarlo con otra cosa.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TaskLogMeta {
+    pub task_id: TaskId,
+    pub job_id: JobId,
+    pub stage: u32,
+    pub partition: u32,
+    pub worker_id: WorkerId,
+    pub log_id: String,
 }
 
 impl AppState {
-    pub fn new() -> Self {
+    pub fn new(wal: Arc<Wal>) -> Self {
         Self {
             tasks_queue: Arc::new(Mutex::new(VecDeque::new())),
             jobs: Arc::new(Mutex::new(HashMap::new())),
             in_flight: Arc::new(Mutex::new(HashMap::new())),
             workers: Arc::new(Mutex::new(HashMap::new())),
             rr_cursor: Arc::new(Mutex::new(0)),
+            pending_stages: Arc::new(Mutex::new(HashMap::new())),
+            stage_outputs: Arc::new(Mutex::new(HashMap::new())),
+            retry_queue: Arc::new(Mutex::new(BinaryHeap::new())),
+            stage_durations_ms: Arc::new(Mutex::new(HashMap::new())),
+            speculated: Arc::new(Mutex::new(HashSet::new())),
+            completed_partitions: Arc::new(Mutex::new(HashSet::new())),
+            background: BackgroundManager::default(),
+            wal,
+            schedules: Arc::new(Mutex::new(HashMap::new())),
+            task_streams: Arc::new(Mutex::new(HashMap::new())),
+            task_logs: Arc::new(Mutex::new(crate::tasklog::load_active_index())),
+            task_progress: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
@@ -44,11 +156,17 @@ pub struct WorkerMeta {
 
     pub last_cpu_percent: Option<f32>,
     pub last_mem_bytes: Option<u64>,
+    pub queue_depth: u32,
+
+    /// Prefijos de `input_path` que este worker reportó tener en disco
+    /// local al registrarse, usados por `assign_task` para priorizar
+    /// asignaciones locality-aware.
+    pub local_paths: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct InFlight {
     pub task: Task,
     pub worker_id: WorkerId,
-    pub started_at: SystemTime,
+    pub dispatched_at: SystemTime,
 }
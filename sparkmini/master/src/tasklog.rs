@@ -0,0 +1,72 @@
+// master/src/tasklog.rs
+//
+// Índice de "tareas activas" del master: un snapshot en disco de
+// `AppState::task_logs` (qué log id le corresponde a cada tarea en vuelo),
+// para que un operador pueda seguir viendo qué estaba corriendo aun si el
+// master se reinicia antes de que esas tareas completen -- el WAL ya
+// reencola la tarea (`wal::replay_into`), pero no conserva el log id viejo,
+// así que sin este índice el log de lo que alcanzó a correr antes del
+// reinicio quedaría inubicable.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
+
+use common::TaskId;
+use tracing::warn;
+
+use crate::state::TaskLogMeta;
+
+fn active_index_path() -> std::path::PathBuf {
+    std::path::Path::new(&common::task_log::task_log_dir()).join("active_tasks.json")
+}
+
+/// Reescribe el índice completo a partir del mapa en memoria. Se llama
+/// cada vez que una tarea arranca o termina (ver `handlers::task_started`/
+/// `handlers::complete_task`), así que el archivo siempre refleja el
+/// último estado conocido sin necesitar compactación aparte.
+pub fn write_active_index(task_logs: &HashMap<TaskId, TaskLogMeta>) {
+    let dir = common::task_log::task_log_dir();
+    if let Err(e) = fs::create_dir_all(&dir) {
+        warn!("tasklog: no se pudo crear {:?}: {:?}", dir, e);
+        return;
+    }
+
+    let entries: Vec<&TaskLogMeta> = task_logs.values().collect();
+    let path = active_index_path();
+    let tmp_path = path.with_extension("json.tmp");
+
+    let result = (|| -> std::io::Result<()> {
+        let mut tmp = File::create(&tmp_path)?;
+        let json = serde_json::to_string_pretty(&entries)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+        tmp.write_all(json.as_bytes())?;
+        tmp.flush()?;
+        fs::rename(&tmp_path, &path)
+    })();
+
+    if let Err(e) = result {
+        warn!("tasklog: no se pudo escribir {:?}: {:?}", path, e);
+    }
+}
+
+/// Relee `active_tasks.json` al arrancar, para que `AppState::task_logs`
+/// no arranque vacío tras un reinicio. Las tareas que ya hayan terminado
+/// mientras el master estaba caído se van a ir sacando del mapa a medida
+/// que sus `complete_task` (ahora dirigidos a un `task_id` que ya no está
+/// en `in_flight`) respondan 404 y el log quede simplemente huérfano.
+pub fn load_active_index() -> HashMap<TaskId, TaskLogMeta> {
+    let path = active_index_path();
+    let file = match File::open(&path) {
+        Ok(f) => f,
+        Err(_) => return HashMap::new(),
+    };
+
+    match serde_json::from_reader::<_, Vec<TaskLogMeta>>(BufReader::new(file)) {
+        Ok(entries) => entries.into_iter().map(|e| (e.task_id.clone(), e)).collect(),
+        Err(e) => {
+            warn!("tasklog: active_tasks.json corrupto, se ignora: {:?}", e);
+            HashMap::new()
+        }
+    }
+}
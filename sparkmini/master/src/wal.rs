@@ -0,0 +1,352 @@
+// master/src/wal.rs
+//
+// Write-ahead log del coordinador: un archivo de solo-apendeo, en JSONL
+// (un evento por línea, igual que los intermedios de `engine.rs`), donde
+// se registra cada evento que modifica `jobs`, `tasks_queue`, `in_flight`
+// o `workers`. Al arrancar, `Wal::replay_into` lo relee de punta a punta
+// para reconstruir el `AppState` antes de levantar el servidor HTTP, así
+// un restart del coordinador no pierde jobs aceptados ni tareas en vuelo.
+//
+// Esta es la capa de persistencia del master: cumple el mismo contrato
+// que tendría una tabla SQL (jobs y tareas sobreviven a un reinicio,
+// lo que estaba `in_flight` se reencola porque su worker puede haber
+// desaparecido; los workers no se reconstruyen, se vuelven a registrar)
+// sin sumar una dependencia nueva de storage; la ruta del
+// archivo se configura con `DATABASE_PATH` (ver `main.rs`).
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use common::{JobId, JobInfo, JobStatus, Task, TaskId, WorkerId};
+
+use crate::background::{BackgroundWorker, WorkerState};
+use crate::state::AppState;
+use crate::MAX_TASK_ATTEMPTS;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum WalEvent {
+    /// Un job nuevo entró al sistema (`create_job`).
+    JobCreated { job: JobInfo },
+    /// Cambiaron los metadatos de un job ya existente (status, contadores,
+    /// reintentos), típicamente desde `complete_task` o el sweep de failover.
+    JobUpdated { job: JobInfo },
+    /// Una o más tareas entraron a `tasks_queue` (tareas iniciales de un
+    /// job, la próxima etapa materializada, o un reencolado por retry).
+    TasksEnqueued { tasks: Vec<Task> },
+    /// Un worker tomó una tarea de la cola (`assign_task`).
+    TaskAssigned { task_id: TaskId, worker_id: WorkerId },
+    /// Un worker reportó el resultado de una tarea (`complete_task`).
+    TaskCompleted { task_id: TaskId, success: bool },
+    /// El sweep de failover marcó un worker como muerto.
+    WorkerDead { worker_id: WorkerId },
+    /// Marca el arranque de un snapshot nuevo (siempre la primera línea
+    /// que escribe `compact`), con un número que sólo crece. Si el archivo
+    /// tuviera, por lo que sea, contenido de más de una compactación (por
+    /// ejemplo un resto de la versión anterior que no llegó a truncarse),
+    /// `replay_into` descarta todo lo anterior al último `Epoch` visto en
+    /// vez de mezclarlo con el snapshot vigente.
+    Epoch { epoch: u64 },
+}
+
+fn epoch_path(wal_path: &Path) -> PathBuf {
+    wal_path.with_extension("epoch")
+}
+
+fn read_epoch(path: &Path) -> u64 {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Escribe el epoch nuevo al sidecar de forma atómica (tmp + rename), igual
+/// que el resto de las escrituras "de una sola vez" del proyecto.
+fn write_epoch(path: &Path, epoch: u64) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("epoch.tmp");
+    std::fs::write(&tmp_path, epoch.to_string())?;
+    std::fs::rename(&tmp_path, path)
+}
+
+/// Log de apendeo del coordinador. El archivo se abre una sola vez al
+/// arrancar y se reapendea desde los handlers a medida que mutan el
+/// estado en memoria.
+pub struct Wal {
+    path: PathBuf,
+    file: Mutex<File>,
+    /// Epoch de la última compactación, persistido en `epoch_path()` para
+    /// sobrevivir reinicios; se incrementa en cada `compact`.
+    epoch: Mutex<u64>,
+}
+
+impl Wal {
+    /// Abre (o crea) el archivo de log en `path`, en modo apendeo.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let epoch = read_epoch(&epoch_path(&path));
+        Ok(Self { path, file: Mutex::new(file), epoch: Mutex::new(epoch) })
+    }
+
+    /// Apendea un evento como una línea JSON. Un fallo de escritura solo
+    /// se loggea: preferimos seguir sirviendo el cluster antes que
+    /// tirarnos abajo por un problema de disco en el WAL.
+    pub fn append(&self, event: WalEvent) {
+        let line = match serde_json::to_string(&event) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("wal: no se pudo serializar el evento: {:?}", e);
+                return;
+            }
+        };
+
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = writeln!(file, "{}", line) {
+            warn!("wal: no se pudo apendear al log ({:?}): {:?}", self.path, e);
+        }
+    }
+
+    /// Relee el log de punta a punta y reconstruye `state` a partir de él.
+    /// Las tareas que habían sido asignadas a un worker pero nunca se
+    /// completaron antes de que el coordinador se cayera vuelven a
+    /// `tasks_queue`, reusando la misma lógica de reintentos que el sweep
+    /// de failover (`attempt += 1`, o el job pasa a `Failed` si ya agotó
+    /// `MAX_TASK_ATTEMPTS`). No reconstruimos `workers`/`in_flight`: cada
+    /// worker se vuelve a registrar con un id nuevo al reconectarse.
+    pub fn replay_into(&self, state: &AppState) {
+        let file = match File::open(&self.path) {
+            Ok(f) => f,
+            Err(_) => {
+                info!("wal: no hay log previo en {:?}, arrancando en limpio", self.path);
+                return;
+            }
+        };
+
+        let mut jobs: HashMap<JobId, JobInfo> = HashMap::new();
+        let mut tasks: HashMap<TaskId, Task> = HashMap::new();
+        let mut assigned: HashSet<TaskId> = HashSet::new();
+        let mut completed: HashSet<TaskId> = HashSet::new();
+        let mut n_events = 0usize;
+        let mut current_epoch: Option<u64> = None;
+
+        for line in BufReader::new(file).lines() {
+            let line = match line {
+                Ok(l) if !l.trim().is_empty() => l,
+                _ => continue,
+            };
+
+            let event: WalEvent = match serde_json::from_str(&line) {
+                Ok(e) => e,
+                Err(e) => {
+                    warn!("wal: línea corrupta ignorada: {:?}", e);
+                    continue;
+                }
+            };
+
+            n_events += 1;
+
+            match event {
+                WalEvent::JobCreated { job } | WalEvent::JobUpdated { job } => {
+                    jobs.insert(job.id.clone(), job);
+                }
+                WalEvent::TasksEnqueued { tasks: new_tasks } => {
+                    for t in new_tasks {
+                        tasks.insert(t.id.clone(), t);
+                    }
+                }
+                WalEvent::TaskAssigned { task_id, .. } => {
+                    assigned.insert(task_id);
+                }
+                WalEvent::TaskCompleted { task_id, .. } => {
+                    completed.insert(task_id);
+                }
+                WalEvent::WorkerDead { .. } => {
+                    // No reconstruimos el registro de workers.
+                }
+                WalEvent::Epoch { epoch } => {
+                    // Arranca un snapshot nuevo: si el archivo tuviera restos
+                    // de una compactación anterior antes de esta línea (p.ej.
+                    // porque el proceso se cayó mientras escribía de más),
+                    // los descartamos en vez de mezclarlos con lo vigente.
+                    if current_epoch.is_some() {
+                        warn!(
+                            "wal: se encontró más de un Epoch en el log, se descarta todo antes del último (epoch={})",
+                            epoch
+                        );
+                    }
+                    current_epoch = Some(epoch);
+                    jobs.clear();
+                    tasks.clear();
+                    assigned.clear();
+                    completed.clear();
+                }
+            }
+        }
+
+        if let Some(epoch) = current_epoch {
+            *self.epoch.lock().unwrap() = epoch;
+        }
+
+        if n_events == 0 {
+            return;
+        }
+
+        let mut to_requeue: Vec<Task> = Vec::new();
+
+        for (task_id, mut task) in tasks {
+            if completed.contains(&task_id) {
+                continue;
+            }
+
+            if assigned.contains(&task_id) {
+                // Estaba en vuelo cuando se cayó el coordinador: la damos
+                // por perdida, igual que el sweep de failover con un
+                // worker que dejó de mandar heartbeats.
+                if task.attempt + 1 > MAX_TASK_ATTEMPTS {
+                    if let Some(job) = jobs.get_mut(&task.job_id) {
+                        job.status = JobStatus::Failed;
+                        job.finished_at = Some(chrono::Utc::now());
+                    }
+                    continue;
+                }
+                task.attempt += 1;
+            }
+
+            to_requeue.push(task);
+        }
+
+        let pending_job_ids: HashSet<JobId> =
+            to_requeue.iter().map(|t| t.job_id.clone()).collect();
+
+        for job in jobs.values_mut() {
+            if matches!(job.status, JobStatus::Running) && !pending_job_ids.contains(&job.id) {
+                warn!(
+                    "wal: job {} quedó RUNNING sin tareas pendientes reconstruidas; lo marcamos SUCCEEDED (best effort, no sabemos si quedaban etapas del DAG sin materializar)",
+                    job.id
+                );
+                job.status = JobStatus::Succeeded;
+                if job.finished_at.is_none() {
+                    job.finished_at = Some(chrono::Utc::now());
+                }
+            }
+        }
+
+        let n_jobs = jobs.len();
+        let n_requeued = to_requeue.len();
+
+        {
+            let mut jobs_map = state.jobs.lock().unwrap();
+            *jobs_map = jobs;
+        }
+
+        {
+            let mut queue = state.tasks_queue.lock().unwrap();
+            for t in to_requeue {
+                queue.push_back(t);
+            }
+        }
+
+        info!(
+            "wal: replay completo ({} evento(s)): {} job(s) reconstruidos, {} tarea(s) reencoladas",
+            n_events, n_jobs, n_requeued
+        );
+    }
+
+    /// Compacta el log: lo reemplaza por el snapshot mínimo necesario para
+    /// reconstruir el estado actual (un `JobCreated` por cada job de
+    /// `state.jobs`, terminado o no, y un solo `TasksEnqueued` con lo que
+    /// sigue pendiente entre cola y vuelo), para que no crezca sin límite
+    /// con los eventos intermedios de cada job.
+    pub fn compact(&self, state: &AppState) {
+        let epoch = {
+            let mut epoch = self.epoch.lock().unwrap();
+            *epoch += 1;
+            *epoch
+        };
+
+        if let Err(e) = write_epoch(&epoch_path(&self.path), epoch) {
+            warn!("wal: no se pudo persistir el epoch {}: {:?}", epoch, e);
+        }
+
+        let mut events = vec![WalEvent::Epoch { epoch }];
+
+        {
+            let jobs = state.jobs.lock().unwrap();
+            for job in jobs.values() {
+                events.push(WalEvent::JobCreated { job: job.clone() });
+            }
+        }
+
+        let mut pending: Vec<Task> = {
+            let queue = state.tasks_queue.lock().unwrap();
+            queue.iter().cloned().collect()
+        };
+
+        {
+            let in_flight = state.in_flight.lock().unwrap();
+            pending.extend(in_flight.values().map(|inf| inf.task.clone()));
+        }
+
+        if !pending.is_empty() {
+            events.push(WalEvent::TasksEnqueued { tasks: pending });
+        }
+
+        let n_events = events.len();
+        let tmp_path = self.path.with_extension("compact.tmp");
+
+        let result = (|| -> std::io::Result<()> {
+            let mut tmp = File::create(&tmp_path)?;
+            for event in &events {
+                let line = serde_json::to_string(event)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+                writeln!(tmp, "{}", line)?;
+            }
+            tmp.flush()?;
+            std::fs::rename(&tmp_path, &self.path)?;
+            Ok(())
+        })();
+
+        match result {
+            Ok(()) => info!("wal: compactado a {} evento(s)", n_events),
+            Err(e) => {
+                warn!("wal: falló la compactación: {:?}", e);
+                return;
+            }
+        }
+
+        // El rename dejó el handle de apendeo viejo apuntando a un inodo
+        // que ya no tiene nombre; lo reabrimos sobre el archivo nuevo.
+        match OpenOptions::new().create(true).append(true).open(&self.path) {
+            Ok(file) => *self.file.lock().unwrap() = file,
+            Err(e) => warn!("wal: no se pudo reabrir el log tras compactar: {:?}", e),
+        }
+    }
+}
+
+/// `BackgroundWorker` que compacta el WAL cada cierto intervalo para que
+/// no crezca sin límite con eventos de jobs ya terminados.
+pub struct WalCompactor;
+
+impl BackgroundWorker for WalCompactor {
+    fn name(&self) -> &str {
+        "wal_compactor"
+    }
+
+    fn step<'a>(
+        &'a mut self,
+        state: &'a AppState,
+    ) -> Pin<Box<dyn Future<Output = WorkerState> + Send + 'a>> {
+        Box::pin(async move {
+            state.wal.compact(state);
+            WorkerState::Active
+        })
+    }
+}
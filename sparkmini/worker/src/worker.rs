@@ -2,55 +2,416 @@ use anyhow::Result;
 use common::{
     Dag,
     JobInfo,
+    JobStatus,
     TaskAssignmentRequest,
     TaskAssignmentResponse,
     TaskCompleteRequest,
+    TaskStartedRequest,
     WorkerHeartbeatRequest,
+    WorkerHeartbeatResponse,
     WorkerRegisterRequest,
     WorkerRegisterResponse,
 };
-use common::engine::WordcountTaskState;
+use common::engine::PipelineState;
 use hostname;
 use reqwest::Client;
 use std::{env, io, time::Duration};
+use tokio::sync::mpsc;
 use tokio::time::sleep;
 use tracing::{info, warn};
 use tracing_subscriber;
 use sysinfo::{CpuExt, System, SystemExt};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
 const DEFAULT_WORKER_CONCURRENCY: u32 = 2;
 
+/// Reintentos por defecto para llamadas HTTP "acotadas" del worker
+/// (asignación de tareas, fetch de job). El registro inicial usa su
+/// propio retry sin límite (ver `send_with_retry` con `max_retries=None`).
+const DEFAULT_WORKER_MAX_RETRIES: u32 = 5;
+/// Backoff base entre reintentos; se duplica en cada intento (acotado).
+const DEFAULT_WORKER_BACKOFF_MS: u64 = 200;
+
+fn worker_max_retries() -> u32 {
+    env::var("WORKER_MAX_RETRIES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_WORKER_MAX_RETRIES)
+}
+
+fn worker_backoff_base() -> Duration {
+    let ms = env::var("WORKER_BACKOFF_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_WORKER_BACKOFF_MS);
+    Duration::from_millis(ms)
+}
+
+/// Reintenta una llamada HTTP con backoff exponencial acotado,
+/// logueando cada intento fallido vía `warn!`. Con `max_retries: None`
+/// reintenta indefinidamente (para el registro inicial: un worker que
+/// arrancó antes que el master tiene que poder sumarse igual apenas
+/// esté arriba). Tanto errores de red como respuestas con status no
+/// exitoso cuentan como intento fallido.
+///
+/// Esto evita que un hipo pasajero del master tire abajo todo el
+/// worker con un `?`, algo que contradice el resto del diseño
+/// (heartbeats, failover) ya pensado para tolerar fallas transitorias.
+async fn send_with_retry<F, Fut>(
+    what: &str,
+    max_retries: Option<u32>,
+    mut f: F,
+) -> Result<reqwest::Response>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = reqwest::Result<reqwest::Response>>,
+{
+    let backoff_base = worker_backoff_base();
+    let mut attempt: u32 = 0;
+
+    loop {
+        let outcome = f().await;
+        attempt += 1;
+
+        match outcome {
+            Ok(resp) if resp.status().is_success() => return Ok(resp),
+            Ok(resp) => {
+                let status = resp.status();
+                if let Some(max) = max_retries {
+                    if attempt > max {
+                        anyhow::bail!("{what}: status {status} tras {attempt} intento(s)");
+                    }
+                }
+                warn!("{} falló con status {} (intento {}), reintentando", what, status, attempt);
+            }
+            Err(e) => {
+                if let Some(max) = max_retries {
+                    if attempt > max {
+                        return Err(e.into());
+                    }
+                }
+                warn!("{} falló ({:?}) (intento {}), reintentando", what, e, attempt);
+            }
+        }
+
+        let delay = backoff_base * 2u32.pow(attempt.min(6) - 1);
+        sleep(delay).await;
+    }
+}
+
+/// Tope de jobs distintos que mantenemos en el cache de DAGs: alcanza de
+/// sobra para la cantidad de jobs que un worker tiene en vuelo a la vez,
+/// y evita que un worker de vida larga lo crezca sin límite.
+const JOB_CACHE_CAPACITY: usize = 64;
+
+/// Cache de `JobInfo` (DAG incluido) por `job_id`, para no pedirle al
+/// master el mismo job una vez por tarea cuando un job fan-out en
+/// cientos de tareas comparten el mismo DAG. Es un LRU simple por orden
+/// de inserción; además no cachea jobs que ya terminaron (no va a haber
+/// más tareas de ellos, así que cachearlos sería puro desperdicio).
+struct JobCache {
+    entries: HashMap<String, JobInfo>,
+    order: VecDeque<String>,
+    capacity: usize,
+}
+
+impl JobCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn get(&self, job_id: &str) -> Option<&JobInfo> {
+        self.entries.get(job_id)
+    }
+
+    fn insert(&mut self, job_id: String, info: JobInfo) {
+        if matches!(info.status, JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled) {
+            // el job ya terminó, no va a pedir más tareas: no vale la pena cachearlo
+            return;
+        }
+
+        if !self.entries.contains_key(&job_id) {
+            if self.entries.len() >= self.capacity {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.entries.remove(&oldest);
+                }
+            }
+            self.order.push_back(job_id.clone());
+        }
+
+        self.entries.insert(job_id, info);
+    }
+}
+
 /// Representa una tarea activa dentro del worker para ejecución en round-robin.
 pub struct ActiveTask {
     pub task: common::Task,
     pub dag: Dag,
-    pub state: WordcountTaskState,
+    pub state: PipelineState,
+    /// Identificador del log de este intento (ver `common::task_log`), ya
+    /// calculado al crear la tarea para no tener que rehacerlo al
+    /// completarla ni al avisarle al master por `/api/v1/tasks/started`.
+    pub log_id: String,
 }
 
 impl ActiveTask {
-    /// Crea una tarea activa a partir del Task y el DAG.
-    /// Nota: por ahora el DAG no se usa dentro de WordcountTaskState,
-    /// pero lo dejamos por si luego quieres soportar más tipos de jobs.
+    /// Crea una tarea activa a partir del Task y el DAG: arma el pipeline
+    /// de operadores que le toca ejecutar a `task.node_id` dentro de `dag`
+    /// (ver `common::engine::PipelineState`), y abre su log de intento.
     pub fn new(task: &common::Task, dag: Dag) -> io::Result<Self> {
-        let input = task.input_path.clone();
-        let output = task.output_path.clone();
-        let state = WordcountTaskState::new(&input, &output)?;
+        let state = PipelineState::new(
+            &dag,
+            &task.node_id,
+            &task.input_path,
+            &task.output_path,
+        )?;
+
+        let log_id = common::task_log::format_task_log_id(
+            &task.job_id,
+            task.stage,
+            task.partition,
+            task.attempt,
+            common::task_log::now_unix(),
+        );
+        let _ = common::task_log::append_line(
+            &log_id,
+            &format!("arranca tarea {} (nodos={})", task.id, task.node_id),
+        );
 
         Ok(Self {
             task: task.clone(),
             dag,
             state,
+            log_id,
         })
     }
 
-    /// Ejecuta un “quantum” sobre la tarea.
+    /// Ejecuta un “quantum” de tiempo sobre la tarea, avanzando el pipeline
+    /// en lotes de registros hasta agotar `quantum` o terminar.
     ///
     /// Devuelve:
     /// - Ok(true)  => la tarea terminó (se debe reportar complete al master).
     /// - Ok(false) => la tarea aún no termina (se reencola en la run queue).
     pub fn step(&mut self, quantum: Duration) -> io::Result<bool> {
-        self.state.step(quantum)
+        let deadline = std::time::Instant::now() + quantum;
+        loop {
+            let done = self.state.step()?;
+            if done || std::time::Instant::now() >= deadline {
+                return Ok(done);
+            }
+        }
+    }
+
+    /// Avance incremental de la tarea en este momento, ver
+    /// `common::engine::PipelineState::progress`.
+    pub fn progress(&self) -> (u64, u64, u64) {
+        self.state.progress()
+    }
+}
+
+/// Reporta al master que una tarea falló (por ejemplo, porque no pudimos
+/// obtener su job o inicializar su pipeline local). `error_kind` viaja tal
+/// cual a `TaskCompleteRequest`: si viene `Some(_)` el master la trata
+/// como no-reintentable (ver `classify_io_error`); `None` deja que el
+/// master reintente con backoff como a cualquier otra falla.
+async fn report_task_failure(client: &Client, base_url: &str, task_id: &str, error_kind: Option<String>) {
+    let complete_url = format!("{}/api/v1/tasks/complete", base_url);
+    let _ = client
+        .post(&complete_url)
+        .json(&TaskCompleteRequest {
+            task_id: task_id.to_string(),
+            success: false,
+            error_kind,
+        })
+        .send()
+        .await;
+}
+
+/// Clasifica un `io::Error` salido de `ActiveTask::new`/`step` para
+/// decidir si vale la pena reintentar la tarea en otro worker o si es un
+/// error de datos/DAG que va a fallar exactamente igual en cualquier
+/// lado. `InvalidInput`/`InvalidData` son justo los que arma el engine
+/// para DAGs mal formados, glob patterns inválidos o records que no
+/// parsean (ver `common::engine`); cualquier otro (IO del filesystem,
+/// etc.) se deja reintentar como antes.
+fn classify_io_error(e: &io::Error) -> Option<String> {
+    match e.kind() {
+        io::ErrorKind::InvalidInput => Some("bad_task".to_string()),
+        io::ErrorKind::InvalidData => Some("malformed_input".to_string()),
+        _ => None,
+    }
+}
+
+/// Reporta al master el avance incremental de una tarea en vuelo (ver
+/// `common::engine::PipelineState::progress`). Best-effort: si el master
+/// no responde, la tarea sigue corriendo igual y el próximo quantum
+/// reintenta con un valor más fresco.
+async fn report_task_progress(client: &Client, base_url: &str, active: &ActiveTask) {
+    let (processed_bytes, total_bytes, processed_records) = active.progress();
+    let progress_url = format!("{}/api/v1/tasks/{}/progress", base_url, active.task.id);
+    let _ = client
+        .post(&progress_url)
+        .json(&common::TaskProgressRequest {
+            task_id: active.task.id.clone(),
+            processed_bytes,
+            total_bytes,
+            processed_records,
+        })
+        .send()
+        .await;
+}
+
+/// Convierte una `Task` recién asignada (por poll o por push) en una
+/// `ActiveTask` encolada para round-robin: trae el `Job` (y su DAG) del
+/// master, sirviéndose de `job_cache` para no repetir ese round-trip por
+/// cada tarea del mismo job, y arma el pipeline. Si algo falla, reporta
+/// la tarea como fallida en vez de dejarla colgada del lado del master.
+async fn onboard_task(
+    client: &Client,
+    base_url: &str,
+    task: common::Task,
+    run_queue: &mut VecDeque<ActiveTask>,
+    job_cache: &mut JobCache,
+) -> Result<()> {
+    info!(
+        "tengo tarea {} del job {} (input={} output={})",
+        task.id, task.job_id, task.input_path, task.output_path
+    );
+
+    let dag = if let Some(cached) = job_cache.get(&task.job_id) {
+        cached.dag.clone()
+    } else {
+        let job_url = format!("{}/api/v1/jobs/{}", base_url, task.job_id);
+        let job_resp = match send_with_retry(
+            "fetch de job",
+            Some(worker_max_retries()),
+            || client.get(&job_url).send(),
+        )
+        .await
+        {
+            Ok(resp) => resp,
+            Err(e) => {
+                warn!(
+                    "no pude obtener job {} para tarea {} tras reintentos: {:?}",
+                    task.job_id, task.id, e
+                );
+                report_task_failure(client, base_url, &task.id, None).await;
+                return Ok(());
+            }
+        };
+
+        let job_info: JobInfo = job_resp.json().await?;
+        let dag = job_info.dag.clone();
+        job_cache.insert(task.job_id.clone(), job_info);
+        dag
+    };
+
+    match ActiveTask::new(&task, dag) {
+        Ok(active) => {
+            // Avisamos al master con qué log id vamos a ir registrando esta
+            // tarea, así puede servir `GET /api/v1/tasks/{id}/log` (o
+            // reconstruir `active_tasks.json` si se reinicia) sin tener que
+            // preguntarnos nada. Best-effort: si esto falla, la tarea igual
+            // se ejecuta, sólo que su log quedará inubicable hasta el próximo
+            // intento.
+            let started_url = format!("{}/api/v1/tasks/started", base_url);
+            let _ = client
+                .post(&started_url)
+                .json(&TaskStartedRequest {
+                    task_id: task.id.clone(),
+                    log_id: active.log_id.clone(),
+                })
+                .send()
+                .await;
+
+            run_queue.push_back(active)
+        }
+        Err(e) => {
+            warn!(
+                "error inicializando ActiveTask para tarea {}: {:?}",
+                task.id, e
+            );
+            report_task_failure(client, base_url, &task.id, classify_io_error(&e)).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Conexión persistente a `GET /api/v1/workers/{id}/stream`: mientras el
+/// master la mantenga abierta, cada tarea que nos empuja llega acá como
+/// un evento SSE (`data: <TaskAssignmentResponse json>`), que reenviamos
+/// por `tx` para que el loop principal la recoja sin esperar a su
+/// próximo poll. Si la conexión se corta (o nunca llega a abrirse), se
+/// reintenta con un backoff fijo; mientras tanto el worker sigue
+/// funcionando igual vía el poll de `/api/v1/tasks/next`.
+async fn run_task_stream(
+    client: Client,
+    base_url: String,
+    worker_id: String,
+    tx: mpsc::UnboundedSender<common::Task>,
+) {
+    let url = format!("{}/api/v1/workers/{}/stream", base_url, worker_id);
+
+    loop {
+        let mut resp = match client.get(&url).send().await {
+            Ok(r) if r.status().is_success() => r,
+            Ok(r) => {
+                warn!("stream de tareas: status {} al conectar, reintento en 2s", r.status());
+                sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+            Err(e) => {
+                warn!("stream de tareas: error conectando ({:?}), reintento en 2s", e);
+                sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+        };
+
+        info!("stream de tareas conectado contra {}", url);
+        let mut buf = String::new();
+
+        loop {
+            match resp.chunk().await {
+                Ok(Some(bytes)) => {
+                    buf.push_str(&String::from_utf8_lossy(&bytes));
+
+                    while let Some(idx) = buf.find("\n\n") {
+                        let frame: String = buf.drain(..idx + 2).collect();
+                        for line in frame.lines() {
+                            let Some(data) = line.strip_prefix("data:") else {
+                                continue;
+                            };
+                            let data = data.trim();
+                            match serde_json::from_str::<TaskAssignmentResponse>(data) {
+                                Ok(TaskAssignmentResponse { task: Some(task) }) => {
+                                    if tx.send(task).is_err() {
+                                        // el loop principal se cayó, no tiene sentido seguir
+                                        return;
+                                    }
+                                }
+                                Ok(_) => {}
+                                Err(e) => warn!("stream de tareas: evento SSE inválido: {:?}", e),
+                            }
+                        }
+                    }
+                }
+                Ok(None) => {
+                    warn!("stream de tareas: el master cerró la conexión, reconectando");
+                    break;
+                }
+                Err(e) => {
+                    warn!("stream de tareas: error leyendo el stream ({:?}), reconectando", e);
+                    break;
+                }
+            }
+        }
+
+        sleep(Duration::from_secs(1)).await;
     }
 }
 
@@ -78,16 +439,26 @@ pub async fn run() -> Result<()> {
 
     let concurrency: usize = max_concurrency as usize;
 
-    // Registro de worker (enviando max_concurrency)
+    // Prefijos de input_path que este worker tiene en disco local, para
+    // que el master prefiera mandarle tareas cuyo input ya tiene cerca.
+    let local_paths: Vec<String> = env::var("WORKER_LOCAL_PATHS")
+        .ok()
+        .map(|s| s.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect())
+        .unwrap_or_default();
+
+    // Registro de worker (enviando max_concurrency y sus paths locales).
+    // Reintenta sin límite: si el worker arrancó antes que el master,
+    // tiene que poder sumarse igual apenas el master esté arriba.
     let register_url = format!("{}/api/v1/workers/register", base_url);
-    let res = client
-        .post(&register_url)
-        .json(&WorkerRegisterRequest {
-            hostname,
-            max_concurrency,
-        })
-        .send()
-        .await?;
+    let register_req = WorkerRegisterRequest {
+        hostname,
+        max_concurrency,
+        local_paths,
+    };
+    let res = send_with_retry("registro en el master", None, || {
+        client.post(&register_url).json(&register_req).send()
+    })
+    .await?;
     let WorkerRegisterResponse { worker_id } = res.json().await?;
 
     info!(
@@ -95,12 +466,26 @@ pub async fn run() -> Result<()> {
         worker_id, concurrency, base_url
     );
 
+    // Conexión persistente de push: el master nos manda tareas por acá
+    // apenas están disponibles, en vez de esperar nuestro próximo poll.
+    // Si se cae, seguimos andando igual por el poll de siempre.
+    let (task_tx, mut task_rx) = mpsc::unbounded_channel::<common::Task>();
+    tokio::spawn(run_task_stream(
+        client.clone(),
+        base_url.clone(),
+        worker_id.clone(),
+        task_tx,
+    ));
+
     // System para leer CPU y memoria
     let mut sys = System::new_all();
 
     // Cola de tareas activas para round-robin
     let mut run_queue: VecDeque<ActiveTask> = VecDeque::new();
 
+    // Cache de JobInfo/DAG por job_id, para no repetir el fetch en cada tarea
+    let mut job_cache = JobCache::new(JOB_CACHE_CAPACITY);
+
     // Quantum por tarea
     let quantum = Duration::from_millis(100);
 
@@ -114,26 +499,76 @@ pub async fn run() -> Result<()> {
         let mem_bytes = sys.used_memory() * 1024;
 
         let hb_url = format!("{}/api/v1/workers/heartbeat", base_url);
-        let _ = client
+        let hb_resp = client
             .post(&hb_url)
             .json(&WorkerHeartbeatRequest {
                 worker_id: worker_id.clone(),
                 cpu_percent,
                 mem_bytes,
+                queue_depth: run_queue.len() as u32,
             })
             .send()
             .await;
 
+        // El master nos devuelve, en cada heartbeat, los jobs cancelados
+        // de los que tenemos alguna tarea en vuelo: abortamos esas tareas
+        // acá mismo (sin esperar a que les toque su turno de RR) y
+        // borramos su output parcial, ya que nadie va a leerlo.
+        if let Ok(resp) = hb_resp {
+            if let Ok(WorkerHeartbeatResponse { cancelled_jobs, .. }) = resp.json().await {
+                if !cancelled_jobs.is_empty() {
+                    let cancelled: std::collections::HashSet<String> =
+                        cancelled_jobs.into_iter().collect();
+                    let mut aborted_task_ids: Vec<String> = Vec::new();
+                    run_queue.retain(|active| {
+                        if cancelled.contains(&active.task.job_id) {
+                            info!(
+                                "tarea {} abortada: job {} fue cancelado",
+                                active.task.id, active.task.job_id
+                            );
+                            let _ = common::task_log::append_line(
+                                &active.log_id,
+                                "tarea abortada: el job fue cancelado",
+                            );
+                            let _ = std::fs::remove_file(&active.task.output_path);
+                            aborted_task_ids.push(active.task.id.clone());
+                            false
+                        } else {
+                            true
+                        }
+                    });
+                    // El master ya sacó el job de Running, así que este
+                    // complete_task va a ser descartado sin reintentar ni
+                    // materializar nada (ver chequeo de job_cancelled en
+                    // `complete_task`); lo que de verdad importa acá es
+                    // que salga de `in_flight`, o el slot de este worker
+                    // queda inflado para siempre.
+                    for task_id in aborted_task_ids {
+                        report_task_failure(&client, &base_url, &task_id, None).await;
+                    }
+                }
+            }
+        }
+
+        // --------- Tareas que nos llegaron por push mientras tanto ---------
+        while let Ok(task) = task_rx.try_recv() {
+            onboard_task(&client, &base_url, task, &mut run_queue, &mut job_cache).await?;
+        }
+
         // --------- Pedir tareas nuevas si hay espacio en la cola ---------
+        // (normalmente esto no encuentra nada porque ya nos las empujaron
+        // por el stream; queda como respaldo si el stream está caído.)
         while run_queue.len() < concurrency {
             let assign_url = format!("{}/api/v1/tasks/next", base_url);
-            let res = client
-                .post(&assign_url)
-                .json(&TaskAssignmentRequest {
-                    worker_id: worker_id.clone(),
-                })
-                .send()
-                .await?;
+            let assign_req = TaskAssignmentRequest {
+                worker_id: worker_id.clone(),
+            };
+            let res = send_with_retry(
+                "pedido de tarea (/api/v1/tasks/next)",
+                Some(worker_max_retries()),
+                || client.post(&assign_url).json(&assign_req).send(),
+            )
+            .await?;
 
             let assignment: TaskAssignmentResponse = res.json().await?;
 
@@ -146,59 +581,7 @@ pub async fn run() -> Result<()> {
                 break;
             };
 
-            info!(
-                "tengo tarea {} del job {} (input={} output={})",
-                task.id, task.job_id, task.input_path, task.output_path
-            );
-
-            // --- 1) Obtener el Job (y el DAG) desde el master ---
-            let job_url = format!("{}/api/v1/jobs/{}", base_url, task.job_id);
-            let job_resp = client.get(&job_url).send().await?;
-
-            if !job_resp.status().is_success() {
-                warn!(
-                    "no pude obtener job {} para tarea {} (status {})",
-                    task.job_id,
-                    task.id,
-                    job_resp.status()
-                );
-                // No tenemos DAG -> reportamos fallo
-                let complete_url = format!("{}/api/v1/tasks/complete", base_url);
-                let _ = client
-                    .post(&complete_url)
-                    .json(&TaskCompleteRequest {
-                        task_id: task.id.clone(),
-                        success: false,
-                    })
-                    .send()
-                    .await;
-                continue;
-            }
-
-            let job_info: JobInfo = job_resp.json().await?;
-            let dag = job_info.dag.clone();
-
-            match ActiveTask::new(&task, dag) {
-                Ok(active) => {
-                    run_queue.push_back(active);
-                }
-                Err(e) => {
-                    warn!(
-                        "error inicializando ActiveTask para tarea {}: {:?}",
-                        task.id, e
-                    );
-                    // Reportamos fallo al master
-                    let complete_url = format!("{}/api/v1/tasks/complete", base_url);
-                    let _ = client
-                        .post(&complete_url)
-                        .json(&TaskCompleteRequest {
-                            task_id: task.id.clone(),
-                            success: false,
-                        })
-                        .send()
-                        .await;
-                }
-            }
+            onboard_task(&client, &base_url, task, &mut run_queue, &mut job_cache).await?;
         }
 
         // --------- Si no hay nada para ejecutar, esperamos un poco ---------
@@ -212,11 +595,13 @@ pub async fn run() -> Result<()> {
             .pop_front()
             .expect("run_queue no debería estar vacía aquí");
         let task_id = active.task.id.clone();
+        let log_id = active.log_id.clone();
 
         match active.step(quantum) {
             Ok(true) => {
                 // La tarea terminó en este quantum
                 info!("terminé tarea {} correctamente (RR)", task_id);
+                let _ = common::task_log::append_line(&log_id, "tarea completada con éxito");
 
                 let complete_url = format!("{}/api/v1/tasks/complete", base_url);
                 let _ = client
@@ -224,17 +609,21 @@ pub async fn run() -> Result<()> {
                     .json(&TaskCompleteRequest {
                         task_id: task_id.clone(),
                         success: true,
+                        error_kind: None,
                     })
                     .send()
                     .await;
             }
             Ok(false) => {
-                // La tarea aún no termina → la reencolamos al final
+                // La tarea aún no termina → avisamos cuánto llevamos y la
+                // reencolamos al final
+                report_task_progress(&client, &base_url, &active).await;
                 run_queue.push_back(active);
             }
             Err(e) => {
                 // Error durante la ejecución de la tarea
                 warn!("error procesando tarea {} en RR: {:?}", task_id, e);
+                let _ = common::task_log::append_line(&log_id, &format!("error: {:?}", e));
 
                 let complete_url = format!("{}/api/v1/tasks/complete", base_url);
                 let _ = client
@@ -242,6 +631,7 @@ pub async fn run() -> Result<()> {
                     .json(&TaskCompleteRequest {
                         task_id: task_id.clone(),
                         success: false,
+                        error_kind: classify_io_error(&e),
                     })
                     .send()
                     .await;